@@ -0,0 +1,12 @@
+use anyhow::Result;
+
+use crate::traits::question_answerer::QuestionAnswerer;
+
+/// For when no model should be loaded at all
+pub struct DisabledAnswerer;
+
+impl QuestionAnswerer for DisabledAnswerer {
+    fn answer(&self, _question: &str) -> Result<String> {
+        Ok("Sorry, I can only help with things around the house.".to_string())
+    }
+}
@@ -0,0 +1,43 @@
+use anyhow::{anyhow, Result};
+use rust_bert::{gpt2::GPT2Generator, pipelines::generation_utils::{GenerateConfig, LanguageGenerator}};
+
+use crate::traits::question_answerer::QuestionAnswerer;
+
+/// Completes the question with GPT-2 running locally. It doesn't follow a
+/// system prompt and can't be interrupted, so neither setting applies.
+pub struct Gpt2Answerer {
+    model: GPT2Generator
+}
+
+impl Gpt2Answerer {
+    pub fn new() -> Result<Self> {
+        let config = GenerateConfig {
+            model_type: rust_bert::pipelines::common::ModelType::GPT2,
+            max_length: Some(30),
+            min_length: 5,
+            length_penalty: 2.0,
+            early_stopping: true,
+            do_sample: false,
+            num_beams: 5,
+            temperature: 0.05,
+            ..Default::default()
+        };
+
+        Ok(Gpt2Answerer { model: GPT2Generator::new(config)? })
+    }
+}
+
+impl QuestionAnswerer for Gpt2Answerer {
+    fn answer(&self, question: &str) -> Result<String> {
+        let output = self.model.generate(Some(&[question]), None)?;
+
+        // The model carries on from the question, only its first sentence is any good
+        output
+            .first()
+            .and_then(|answer| answer.text.trim_start_matches(question).split('.').next())
+            .map(str::trim)
+            .filter(|answer| !answer.is_empty())
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("GPT-2 came up with nothing"))
+    }
+}
@@ -0,0 +1,4 @@
+pub mod disabled_answerer;
+#[cfg(feature = "transformers")]
+pub mod gpt2_answerer;
+pub mod openai_answerer;
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use crate::{model::settings::{OpenAiConfig, QuestionAnsweringConfig}, traits::question_answerer::QuestionAnswerer};
+
+/// Asks a server with an OpenAI compatible chat completions API, usually a
+/// llama.cpp or ollama server on the local network
+pub struct OpenAiAnswerer {
+    agent: ureq::Agent,
+    url: String,
+    model: String,
+    api_key: Option<String>,
+    system_prompt: String,
+    max_tokens: usize
+}
+
+impl OpenAiAnswerer {
+    pub fn new(config: &OpenAiConfig, settings: &QuestionAnsweringConfig) -> Self {
+        OpenAiAnswerer {
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(settings.timeout)).build(),
+            url: format!("{}/chat/completions", config.url.trim_end_matches('/')),
+            model: config.model.clone(),
+            api_key: config.api_key.clone(),
+            system_prompt: settings.system_prompt.clone(),
+            // A word is a token or two, the answer gets trimmed to whole sentences later anyway
+            max_tokens: settings.max_words * 2
+        }
+    }
+}
+
+impl QuestionAnswerer for OpenAiAnswerer {
+    fn answer(&self, question: &str) -> Result<String> {
+        let mut request = self.agent.post(&self.url);
+        if let Some(ref api_key) = self.api_key {
+            request = request.set("Authorization", &format!("Bearer {}", api_key));
+        }
+
+        let response: Value = request
+            .send_json(json!({
+                "model": self.model,
+                "max_tokens": self.max_tokens,
+                "messages": [
+                    { "role": "system", "content": self.system_prompt },
+                    { "role": "user", "content": question }
+                ]
+            }))
+            .map_err(|e| anyhow!("Could not reach {}: {}", self.url, e))?
+            .into_json()?;
+
+        response["choices"][0]["message"]["content"]
+            .as_str()
+            .map(|answer| answer.trim().to_string())
+            .ok_or_else(|| anyhow!("Unexpected response from {}: {}", self.url, response))
+    }
+}
@@ -0,0 +1,40 @@
+use std::{collections::{BTreeMap, HashMap}, sync::Arc};
+
+use anyhow::Result;
+
+use crate::{core::state_store::StateStore, model::{backend_config::{BackendConfig, LOG_BACKEND}, device::Device}, traits::executor_backend::ExecutorBackend};
+
+use super::{home_assistant_backend::HomeAssistantBackend, http_backend::HttpBackend, log_backend::LogBackend, mqtt_backend::MqttBackend, shell_backend::ShellBackend, simulated_backend::SimulatedBackend};
+
+/// The backends declared in the command map, looked up by name
+pub struct BackendRegistry {
+    backends: HashMap<String, Box<dyn ExecutorBackend>>
+}
+
+impl BackendRegistry {
+    pub fn new(configs: &BTreeMap<String, BackendConfig>, devices: &[Device], states: Arc<StateStore>) -> Result<Self> {
+        let mut backends: HashMap<String, Box<dyn ExecutorBackend>> = HashMap::new();
+        backends.insert(LOG_BACKEND.to_string(), Box::new(LogBackend));
+
+        for (name, config) in configs {
+            let backend: Box<dyn ExecutorBackend> = match config {
+                BackendConfig::Log => Box::new(LogBackend),
+                BackendConfig::Simulated => Box::new(SimulatedBackend::new()),
+                BackendConfig::Mqtt(config) => Box::new(MqttBackend::new(name, config, devices, states.clone())?),
+                BackendConfig::HomeAssistant(config) => Box::new(HomeAssistantBackend::new(config, states.clone())),
+                BackendConfig::Http(config) => Box::new(HttpBackend::new(config)),
+                BackendConfig::Shell(config) => Box::new(ShellBackend::new(config))
+            };
+
+            backends.insert(name.clone(), backend);
+        }
+
+        Ok(BackendRegistry {
+            backends
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn ExecutorBackend> {
+        self.backends.get(name).map(|backend| backend.as_ref())
+    }
+}
@@ -0,0 +1,222 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::{core::state_store::StateStore, errors::execution_error::ExecutionError, model::{backend_config::HomeAssistantConfig, command_action::{CommandAction, CommandGradientValue, CommandSwitchValue}, command_subject::SubjectDefinition, device::{Device, DeviceAddress}, device_state::{percent, switch_state, DeviceState}}, traits::executor_backend::ExecutorBackend};
+
+use super::http_backend::transport_error;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Renders every entity that belongs to an area as a list of [area, entity_id] pairs
+const AREA_ENTITIES_TEMPLATE: &str = "\
+{% set ns = namespace(pairs=[]) %}\
+{% for area in areas() %}{% for entity in area_entities(area) %}\
+{% set ns.pairs = ns.pairs + [[area_name(area), entity]] %}\
+{% endfor %}{% endfor %}\
+{{ ns.pairs | tojson }}";
+
+/// Talks to the Home Assistant REST API. Calls are blocking so
+/// they can also be made while the command map is being loaded.
+#[derive(Clone)]
+pub struct HomeAssistantClient {
+    agent: ureq::Agent,
+    url: String,
+    token: String
+}
+
+impl HomeAssistantClient {
+    pub fn new(config: &HomeAssistantConfig) -> Self {
+        HomeAssistantClient {
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+            url: config.url.trim_end_matches('/').to_string(),
+            token: config.token.clone()
+        }
+    }
+
+    /// Calls a service and returns the states it changed
+    pub fn call_service(&self, domain: &str, service: &str, data: Value) -> Result<Value, ExecutionError> {
+        let response = self.agent
+            .post(&format!("{}/api/services/{}/{}", self.url, domain, service))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .send_json(data);
+
+        json(checked(response)?)
+    }
+
+    pub fn state(&self, entity_id: &str) -> Result<Value, ExecutionError> {
+        let response = self.agent
+            .get(&format!("{}/api/states/{}", self.url, entity_id))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .call();
+
+        json(checked(response)?)
+    }
+
+    /// All the entities assigned to an area, as (area name, entity id) pairs
+    pub fn area_entities(&self) -> Result<Vec<(String, String)>, ExecutionError> {
+        let response = self.agent
+            .post(&format!("{}/api/template", self.url))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .send_json(json!({ "template": AREA_ENTITIES_TEMPLATE }));
+
+        let rendered = checked(response)?
+            .into_string()
+            .map_err(|e| ExecutionError::failed(e.to_string()))?;
+
+        serde_json::from_str(&rendered).map_err(|e| ExecutionError::failed(e.to_string()))
+    }
+}
+
+// Home Assistant explains what went wrong in the body, keep it in the error
+fn checked(response: Result<ureq::Response, ureq::Error>) -> Result<ureq::Response, ExecutionError> {
+    match response {
+        Ok(response) => Ok(response),
+        Err(ureq::Error::Status(status, response)) => Err(ExecutionError::rejected(format!(
+            "Home Assistant responded with {}: {}",
+            status,
+            response.into_string().unwrap_or_default().trim()
+        ))),
+        Err(e) => Err(transport_error(e))
+    }
+}
+
+fn json(response: ureq::Response) -> Result<Value, ExecutionError> {
+    response
+        .into_json()
+        .map_err(|e| ExecutionError::failed(format!("Unexpected response from Home Assistant: {}", e)))
+}
+
+/// Maps commands to Home Assistant service calls on the device's entity
+pub struct HomeAssistantBackend {
+    client: HomeAssistantClient,
+    states: Arc<StateStore>
+}
+
+impl HomeAssistantBackend {
+    pub fn new(config: &HomeAssistantConfig, states: Arc<StateStore>) -> Self {
+        HomeAssistantBackend {
+            client: HomeAssistantClient::new(config),
+            states
+        }
+    }
+}
+
+#[async_trait]
+impl ExecutorBackend for HomeAssistantBackend {
+    async fn execute(&self, device: &Device, action: &CommandAction, subject: &SubjectDefinition) -> Result<(), ExecutionError> {
+        let DeviceAddress::EntityId(ref entity_id) = device.address else {
+            return Err(ExecutionError::failed(format!("Device '{}' has no Home Assistant entity id", device.id)));
+        };
+
+        let client = self.client.clone();
+        let entity_id = entity_id.clone();
+        let action = *action;
+        let subject = subject.clone();
+
+        // The client blocks so it's kept off the async workers
+        let (entity_id, changed) = tokio::task::spawn_blocking(move || {
+            let (domain, service, data) = service_call(&client, &entity_id, action, &subject)?;
+            let changed = client.call_service(domain, service, data)?;
+            Ok::<_, ExecutionError>((entity_id, changed))
+        })
+        .await
+        .map_err(|e| ExecutionError::failed(e.to_string()))??;
+
+        let state = changed
+            .as_array()
+            .and_then(|states| states.iter().find(|state| state["entity_id"] == entity_id.as_str()));
+
+        match state {
+            Some(state) => {
+                println!("Home Assistant reports '{}' is now {}", entity_id, state["state"]);
+                self.states.report(&device.id, reported_state(state));
+            }
+            None => println!("Home Assistant accepted the call but '{}' didn't change", entity_id)
+        };
+
+        Ok(())
+    }
+}
+
+// Picks the service for the entity's domain. Relative changes of covers and
+// climate devices need the current value so it's read from the entity first.
+// Levels move by the subject's step and stay within its range.
+fn service_call(
+    client: &HomeAssistantClient,
+    entity_id: &str,
+    action: CommandAction,
+    subject: &SubjectDefinition
+) -> Result<(&'static str, &'static str, Value), ExecutionError> {
+    let domain = entity_id.split('.').next().unwrap_or_default();
+    let target = json!({ "entity_id": entity_id });
+
+    let call = match (domain, action) {
+        ("light", CommandAction::Switch(CommandSwitchValue::On)) => ("light", "turn_on", target),
+        ("light", CommandAction::Switch(CommandSwitchValue::Off) | CommandAction::Gradient(CommandGradientValue::Min)) => ("light", "turn_off", target),
+        ("light", CommandAction::Gradient(CommandGradientValue::Max)) => ("light", "turn_on", with(target, "brightness_pct", json!(subject.max))),
+        ("light", CommandAction::Gradient(CommandGradientValue::More)) => ("light", "turn_on", with(target, "brightness_step_pct", json!(subject.step))),
+        ("light", CommandAction::Gradient(CommandGradientValue::Less)) => ("light", "turn_on", with(target, "brightness_step_pct", json!(-subject.step))),
+        ("light", CommandAction::Gradient(CommandGradientValue::Set(level))) => ("light", "turn_on", with(target, "brightness_pct", json!(subject.clamp(level)))),
+
+        ("cover", CommandAction::Switch(CommandSwitchValue::On) | CommandAction::Gradient(CommandGradientValue::Max)) => ("cover", "open_cover", target),
+        ("cover", CommandAction::Switch(CommandSwitchValue::Off) | CommandAction::Gradient(CommandGradientValue::Min)) => ("cover", "close_cover", target),
+        ("cover", CommandAction::Gradient(CommandGradientValue::Set(position))) => ("cover", "set_cover_position", with(target, "position", json!(subject.clamp(position)))),
+        ("cover", CommandAction::Gradient(value)) => {
+            let position = attribute(client, entity_id, "current_position")?.round() as i32;
+            let step = if value == CommandGradientValue::More { subject.step } else { -subject.step };
+            ("cover", "set_cover_position", with(target, "position", json!(subject.clamp(position + step))))
+        }
+
+        ("climate", CommandAction::Switch(CommandSwitchValue::On)) => ("climate", "turn_on", target),
+        ("climate", CommandAction::Switch(CommandSwitchValue::Off)) => ("climate", "turn_off", target),
+        ("climate", CommandAction::Gradient(value)) => {
+            let (min, max, step) = (subject.min as f64, subject.max as f64, subject.step as f64);
+            let temperature = match value {
+                CommandGradientValue::Min => min,
+                CommandGradientValue::Max => max,
+                CommandGradientValue::More => attribute(client, entity_id, "temperature")? + step,
+                CommandGradientValue::Less => attribute(client, entity_id, "temperature")? - step,
+                CommandGradientValue::Set(temperature) => temperature as f64
+            };
+            ("climate", "set_temperature", with(target, "temperature", json!(temperature.clamp(min, max))))
+        }
+
+        ("fan", CommandAction::Gradient(CommandGradientValue::More)) => ("fan", "increase_speed", target),
+        ("fan", CommandAction::Gradient(CommandGradientValue::Less)) => ("fan", "decrease_speed", target),
+        ("fan", CommandAction::Gradient(CommandGradientValue::Set(percentage))) => ("fan", "set_percentage", with(target, "percentage", json!(subject.clamp(percentage)))),
+
+        // Anything that can be switched understands the generic services
+        (_, CommandAction::Switch(CommandSwitchValue::On) | CommandAction::Gradient(CommandGradientValue::Max)) => ("homeassistant", "turn_on", target),
+        (_, CommandAction::Switch(CommandSwitchValue::Off) | CommandAction::Gradient(CommandGradientValue::Min)) => ("homeassistant", "turn_off", target),
+        (_, CommandAction::Switch(CommandSwitchValue::Toggle)) => ("homeassistant", "toggle", target),
+        (_, CommandAction::Gradient(_)) => return Err(ExecutionError::unsupported(format!("Home Assistant can't change the level of '{}'", entity_id)))
+    };
+
+    Ok(call)
+}
+
+fn reported_state(state: &Value) -> DeviceState {
+    let attributes = &state["attributes"];
+
+    DeviceState {
+        on: state["state"].as_str().and_then(switch_state),
+        level: attributes["brightness"].as_f64().map(|brightness| percent(brightness, 255.0))
+            .or_else(|| attributes["current_position"].as_f64().map(|position| percent(position, 100.0)))
+            .or_else(|| attributes["percentage"].as_f64().map(|percentage| percent(percentage, 100.0)))
+            // Climate devices report their target in degrees
+            .or_else(|| attributes["temperature"].as_f64().map(|temperature| temperature.round() as i32))
+    }
+}
+
+fn with(mut data: Value, key: &str, value: Value) -> Value {
+    data[key] = value;
+    data
+}
+
+fn attribute(client: &HomeAssistantClient, entity_id: &str, name: &str) -> Result<f64, ExecutionError> {
+    client.state(entity_id)?["attributes"][name]
+        .as_f64()
+        .ok_or_else(|| ExecutionError::unsupported(format!("'{}' doesn't report its {}", entity_id, name)))
+}
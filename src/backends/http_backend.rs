@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::{errors::execution_error::ExecutionError, model::{backend_config::HttpConfig, command_action::CommandAction, command_subject::SubjectDefinition, device::{Device, DeviceAddress}}, traits::executor_backend::ExecutorBackend};
+
+use super::template::render;
+
+/// Calls a webhook for every command, handy for homemade gadgets
+pub struct HttpBackend {
+    agent: ureq::Agent,
+    method: String,
+    headers: Vec<(String, String)>,
+    body: Option<String>
+}
+
+impl HttpBackend {
+    pub fn new(config: &HttpConfig) -> Self {
+        HttpBackend {
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(config.timeout)).build(),
+            method: config.method.to_uppercase(),
+            headers: config.headers.clone().into_iter().collect(),
+            body: config.body.clone()
+        }
+    }
+}
+
+#[async_trait]
+impl ExecutorBackend for HttpBackend {
+    async fn execute(&self, device: &Device, action: &CommandAction, _subject: &SubjectDefinition) -> Result<(), ExecutionError> {
+        let DeviceAddress::Url(ref url) = device.address else {
+            return Err(ExecutionError::failed(format!("Device '{}' has no url", device.id)));
+        };
+
+        let mut request = self.agent.request(&self.method, &render(url, device, action));
+        for (name, value) in &self.headers {
+            request = request.set(name, &render(value, device, action));
+        }
+        let body = self.body.as_ref().map(|body| render(body, device, action));
+
+        // ureq blocks so the request is kept off the async workers
+        let status = tokio::task::spawn_blocking(move || send(request, body))
+            .await
+            .map_err(|e| ExecutionError::failed(e.to_string()))??;
+
+        println!("Webhook for '{}' responded with {}", device.id, status);
+        Ok(())
+    }
+}
+
+fn send(request: ureq::Request, body: Option<String>) -> Result<u16, ExecutionError> {
+    let response = match body {
+        Some(body) => request.send_string(&body),
+        None => request.call()
+    };
+
+    match response {
+        Ok(response) => Ok(response.status()),
+        Err(ureq::Error::Status(status, response)) => Err(ExecutionError::rejected(format!(
+            "Webhook responded with {}: {}",
+            status,
+            response.into_string().unwrap_or_default().trim()
+        ))),
+        Err(e) => Err(transport_error(e))
+    }
+}
+
+/// Tells a server that isn't there apart from one that's too slow
+pub fn transport_error(error: ureq::Error) -> ExecutionError {
+    let timed_out = match error {
+        ureq::Error::Transport(ref transport) => std::error::Error::source(transport)
+            .and_then(|source| source.downcast_ref::<std::io::Error>())
+            .is_some_and(|e| matches!(e.kind(), std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock)),
+        ureq::Error::Status(..) => false
+    };
+
+    if timed_out {
+        ExecutionError::timed_out(error.to_string())
+    } else {
+        ExecutionError::unreachable(error.to_string())
+    }
+}
@@ -0,0 +1,14 @@
+use async_trait::async_trait;
+
+use crate::{errors::execution_error::ExecutionError, model::{command_action::CommandAction, command_subject::SubjectDefinition, device::Device}, traits::executor_backend::ExecutorBackend};
+
+/// Doesn't drive anything, only reports what would have happened
+pub struct LogBackend;
+
+#[async_trait]
+impl ExecutorBackend for LogBackend {
+    async fn execute(&self, device: &Device, action: &CommandAction, _subject: &SubjectDefinition) -> Result<(), ExecutionError> {
+        println!("Would execute {:?} on '{}' at {:?}", action, device.id, device.address);
+        Ok(())
+    }
+}
@@ -0,0 +1,8 @@
+pub mod backend_registry;
+pub mod home_assistant_backend;
+pub mod http_backend;
+pub mod log_backend;
+pub mod mqtt_backend;
+pub mod shell_backend;
+pub mod simulated_backend;
+pub mod template;
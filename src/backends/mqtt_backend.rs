@@ -0,0 +1,282 @@
+use std::{collections::{BTreeMap, HashMap}, sync::{atomic::{AtomicBool, Ordering}, Arc}, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS, TlsConfiguration, Transport};
+use serde_json::Value;
+use tokio::task::JoinHandle;
+
+use crate::{core::state_store::StateStore, errors::execution_error::ExecutionError, model::{backend_config::{MqttConfig, MqttTlsConfig}, command_action::CommandAction, command_subject::SubjectDefinition, device::{Device, DeviceAddress}, device_state::{percent, switch_state, DeviceState}}, traits::{executor_backend::ExecutorBackend, labelable::Labelable}};
+
+use super::template::render;
+
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(5);
+const REQUEST_CAPACITY: usize = 16;
+
+// Zigbee2MQTT style payloads, anything else can be configured with `payloads`
+const DEFAULT_PAYLOADS: [(&str, &str); 7] = [
+    ("turn on", r#"{"state":"ON"}"#),
+    ("turn off", r#"{"state":"OFF"}"#),
+    ("increase", r#"{"brightness_step":32}"#),
+    ("decrease", r#"{"brightness_step":-32}"#),
+    ("open", r#"{"state":"OPEN"}"#),
+    ("close", r#"{"state":"CLOSE"}"#),
+    ("toggle", r#"{"state":"TOGGLE"}"#)
+];
+
+// Setting a level has no sensible default, it depends too much on the device
+const SET_PAYLOAD: &str = "set";
+
+/// Publishes a payload to the device's topic. Payloads are templates, see
+/// `template::render` for the placeholders. Devices with a `state_topic`
+/// have their reports tracked in the state store.
+pub struct MqttBackend {
+    client: AsyncClient,
+    connection: JoinHandle<()>,
+    connected: Arc<AtomicBool>,
+    qos: QoS,
+    retain: bool,
+    payloads: BTreeMap<String, String>
+}
+
+impl MqttBackend {
+    pub fn new(name: &str, config: &MqttConfig, devices: &[Device], states: Arc<StateStore>) -> Result<Self> {
+        let qos = rumqttc::qos(config.qos)
+            .map_err(|_| anyhow!("Backend '{}' has an invalid qos {}, expected 0, 1 or 2", name, config.qos))?;
+        let payloads = payloads(name, config)?;
+
+        let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+        options.set_keep_alive(KEEP_ALIVE);
+
+        match (&config.username, &config.password) {
+            (Some(username), password) => {
+                options.set_credentials(username, password.clone().unwrap_or_default());
+            }
+            (None, Some(_)) => return Err(anyhow!("Backend '{}' has a password but no username", name)),
+            (None, None) => {}
+        };
+
+        if let Some(ref tls) = config.tls {
+            options.set_transport(Transport::tls_with_config(tls_configuration(tls)?));
+        }
+
+        let (client, mut event_loop) = AsyncClient::new(options, REQUEST_CAPACITY);
+
+        let mut state_topics: HashMap<String, Vec<String>> = HashMap::new();
+        for device in devices.iter().filter(|device| device.backend == name) {
+            if let Some(ref topic) = device.state_topic {
+                state_topics.entry(topic.clone()).or_default().push(device.id.clone());
+            }
+        }
+
+        // The event loop has to be polled for anything to be sent, it also reconnects
+        // on its own after the broker goes away
+        let host = config.host.clone();
+        let connected = Arc::new(AtomicBool::new(false));
+        let connection_state = connected.clone();
+        let subscriber = client.clone();
+        let connection = tokio::spawn(async move {
+            loop {
+                match event_loop.poll().await {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        connection_state.store(true, Ordering::Relaxed);
+
+                        // Subscriptions don't survive a reconnect. Awaiting here would
+                        // stop the event loop from being polled so we don't.
+                        for topic in state_topics.keys() {
+                            if let Err(e) = subscriber.try_subscribe(topic, QoS::AtMostOnce) {
+                                eprintln!("Could not subscribe to {}: {}", topic, e);
+                            }
+                        }
+                    }
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        for device_id in state_topics.get(&publish.topic).into_iter().flatten() {
+                            states.report(device_id, reported_state(&publish.payload));
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        connection_state.store(false, Ordering::Relaxed);
+                        eprintln!("MQTT connection to {} failed: {}", host, e);
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                    }
+                };
+            }
+        });
+
+        Ok(MqttBackend {
+            client,
+            connection,
+            connected,
+            qos,
+            retain: config.retain,
+            payloads
+        })
+    }
+
+    fn payload(&self, device: &Device, action: &CommandAction) -> Option<String> {
+        self.payloads
+            .get(action.label())
+            .map(|template| render(template, device, action))
+    }
+}
+
+impl Drop for MqttBackend {
+    fn drop(&mut self) {
+        self.connection.abort();
+    }
+}
+
+#[async_trait]
+impl ExecutorBackend for MqttBackend {
+    fn supports_levels(&self) -> bool {
+        self.payloads.contains_key(SET_PAYLOAD)
+    }
+
+    async fn execute(&self, device: &Device, action: &CommandAction, _subject: &SubjectDefinition) -> Result<(), ExecutionError> {
+        let DeviceAddress::Topic(ref topic) = device.address else {
+            return Err(ExecutionError::failed(format!("Device '{}' has no MQTT topic", device.id)));
+        };
+
+        let payload = self
+            .payload(device, action)
+            .ok_or_else(|| ExecutionError::unsupported(format!("No MQTT payload for {:?}", action)))?;
+
+        // Publishing only queues the message, without a connection it would never arrive
+        if !self.connected.load(Ordering::Relaxed) {
+            return Err(ExecutionError::unreachable("Not connected to the MQTT broker"));
+        }
+
+        tokio::time::timeout(PUBLISH_TIMEOUT, self.client.publish(topic, self.qos, self.retain, payload))
+            .await
+            .map_err(|_| ExecutionError::timed_out(format!("Timed out publishing to {}", topic)))?
+            .map_err(|e| ExecutionError::unreachable(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+fn payloads(name: &str, config: &MqttConfig) -> Result<BTreeMap<String, String>> {
+    let labels = CommandAction::labels();
+    if let Some(label) = config.payloads.keys().find(|label| !labels.contains(label) && *label != SET_PAYLOAD) {
+        return Err(anyhow!("Backend '{}' has a payload for unknown action '{}'", name, label));
+    }
+
+    let mut payloads: BTreeMap<String, String> = DEFAULT_PAYLOADS
+        .iter()
+        .map(|(label, payload)| (label.to_string(), payload.to_string()))
+        .collect();
+    payloads.extend(config.payloads.clone());
+
+    Ok(payloads)
+}
+
+// Understands Zigbee2MQTT style JSON reports as well as plain ON/OFF or a number
+fn reported_state(payload: &[u8]) -> DeviceState {
+    let payload = String::from_utf8_lossy(payload);
+    let payload = payload.trim();
+
+    match serde_json::from_str::<Value>(payload) {
+        Ok(Value::Object(report)) => DeviceState {
+            on: report.get("state").and_then(Value::as_str).and_then(switch_state),
+            level: report.get("brightness").and_then(Value::as_f64).map(|brightness| percent(brightness, 254.0))
+                .or_else(|| report.get("position").and_then(Value::as_f64).map(|position| percent(position, 100.0)))
+        },
+        Ok(Value::Number(level)) => DeviceState {
+            on: None,
+            level: level.as_f64().map(|level| level.round() as i32)
+        },
+        _ => DeviceState {
+            on: switch_state(payload),
+            level: None
+        }
+    }
+}
+
+fn tls_configuration(tls: &MqttTlsConfig) -> Result<TlsConfiguration> {
+    let read = |path: &std::path::PathBuf| std::fs::read(path)
+        .with_context(|| format!("Could not read {}", path.display()));
+
+    let client_auth = match (&tls.client_cert, &tls.client_key) {
+        (Some(cert), Some(key)) => Some((read(cert)?, read(key)?)),
+        (None, None) => None,
+        _ => return Err(anyhow!("MQTT client authentication needs both `client_cert` and `client_key`"))
+    };
+
+    Ok(TlsConfiguration::Simple {
+        ca: read(&tls.ca_file)?,
+        alpn: None,
+        client_auth
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{command_action::CommandGradientValue, command_subject::CommandSubject};
+
+    use super::*;
+
+    fn config(payloads: &str) -> MqttConfig {
+        serde_yaml::from_str(&format!("host: localhost\npayloads: {}", payloads)).unwrap()
+    }
+
+    fn lamp() -> Device {
+        Device {
+            id: "desk-lamp".to_string(),
+            location: "office".to_string(),
+            subject: CommandSubject { label: "light".to_string(), name: "light".to_string() },
+            backend: "mqtt".to_string(),
+            address: DeviceAddress::Topic("zigbee2mqtt/desk-lamp/set".to_string()),
+            state_topic: None
+        }
+    }
+
+    #[test]
+    fn zigbee2mqtt_payloads_are_the_default() {
+        let payloads = payloads("mqtt", &config("{}")).unwrap();
+
+        assert_eq!(payloads["turn on"], r#"{"state":"ON"}"#);
+        assert_eq!(payloads["decrease"], r#"{"brightness_step":-32}"#);
+        assert!(!payloads.contains_key(SET_PAYLOAD));
+    }
+
+    #[test]
+    fn configured_payloads_replace_the_defaults() {
+        let payloads = payloads("mqtt", &config(r#"{"turn on": "1", "set": "{value}"}"#)).unwrap();
+
+        assert_eq!(payloads["turn on"], "1");
+        assert_eq!(payloads["turn off"], r#"{"state":"OFF"}"#);
+        assert_eq!(payloads[SET_PAYLOAD], "{value}");
+    }
+
+    #[test]
+    fn a_payload_for_an_unknown_action_is_rejected() {
+        let error = payloads("mqtt", &config(r#"{"dim": "1"}"#)).unwrap_err();
+
+        assert_eq!(error.to_string(), "Backend 'mqtt' has a payload for unknown action 'dim'");
+    }
+
+    #[test]
+    fn the_set_payload_is_filled_in_with_the_level() {
+        let payloads = payloads("mqtt", &config(r#"{"set": '{"brightness_percent":{value},"device":"{device}"}'}"#)).unwrap();
+        let action = CommandAction::Gradient(CommandGradientValue::Set(40));
+
+        assert_eq!(render(&payloads[action.label()], &lamp(), &action), r#"{"brightness_percent":40,"device":"desk-lamp"}"#);
+    }
+
+    #[test]
+    fn json_reports_give_the_state_and_level() {
+        assert_eq!(reported_state(br#"{"state":"ON","brightness":127}"#), DeviceState { on: Some(true), level: Some(50) });
+        assert_eq!(reported_state(br#"{"state":"CLOSE","position":30}"#), DeviceState { on: Some(false), level: Some(30) });
+        assert_eq!(reported_state(br#"{"linkquality":80}"#), DeviceState::default());
+    }
+
+    #[test]
+    fn plain_reports_are_a_state_or_a_level() {
+        assert_eq!(reported_state(b" OFF\n"), DeviceState { on: Some(false), level: None });
+        assert_eq!(reported_state(b"21.6"), DeviceState { on: None, level: Some(22) });
+        assert_eq!(reported_state(b"unavailable"), DeviceState::default());
+    }
+}
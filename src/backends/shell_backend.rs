@@ -0,0 +1,62 @@
+use std::{process::Stdio, time::Duration};
+
+use async_trait::async_trait;
+use tokio::process::Command;
+
+use crate::{errors::execution_error::ExecutionError, model::{backend_config::ShellConfig, command_action::CommandAction, command_subject::SubjectDefinition, device::Device}, traits::executor_backend::ExecutorBackend};
+
+use super::template::render;
+
+/// Runs a command for every action. The arguments can contain placeholders and
+/// the details are also passed in the JARVIS_* env variables.
+pub struct ShellBackend {
+    command: String,
+    args: Vec<String>,
+    timeout: Duration
+}
+
+impl ShellBackend {
+    pub fn new(config: &ShellConfig) -> Self {
+        ShellBackend {
+            command: config.command.clone(),
+            args: config.args.clone(),
+            timeout: Duration::from_secs(config.timeout)
+        }
+    }
+}
+
+#[async_trait]
+impl ExecutorBackend for ShellBackend {
+    async fn execute(&self, device: &Device, action: &CommandAction, _subject: &SubjectDefinition) -> Result<(), ExecutionError> {
+        let child = Command::new(&self.command)
+            .args(self.args.iter().map(|arg| render(arg, device, action)))
+            .env("JARVIS_DEVICE", &device.id)
+            .env("JARVIS_LOCATION", &device.location)
+            .env("JARVIS_SUBJECT", &device.subject.label)
+            .env("JARVIS_ACTION", action.label())
+            .env("JARVIS_VALUE", action.value())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // Dropped on timeout, this makes sure it doesn't linger
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| ExecutionError::failed(format!("Could not run {}: {}", self.command, e)))?;
+
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output())
+            .await
+            .map_err(|_| ExecutionError::timed_out(format!("{} timed out after {}s", self.command, self.timeout.as_secs())))?
+            .map_err(|e| ExecutionError::failed(e.to_string()))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(match stderr.trim() {
+                "" => ExecutionError::rejected(format!("{} failed with {}", self.command, output.status)),
+                stderr => ExecutionError::rejected(format!("{} failed with {}: {}", self.command, output.status, stderr))
+            });
+        }
+
+        println!("{} for '{}' finished: {}", self.command, device.id, String::from_utf8_lossy(&output.stdout).trim());
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+
+use crate::{errors::execution_error::ExecutionError, model::{command_action::{CommandAction, CommandGradientValue, CommandSwitchValue}, command_subject::SubjectDefinition, device::Device}, traits::executor_backend::ExecutorBackend};
+
+#[derive(Debug, Default, Clone, Copy)]
+struct SimulatedDevice {
+    on: bool,
+    level: i32
+}
+
+/// An in-memory home. Handy for trying out a command map
+/// before any real devices are hooked up.
+pub struct SimulatedBackend {
+    devices: Mutex<HashMap<String, SimulatedDevice>>
+}
+
+impl SimulatedBackend {
+    pub fn new() -> Self {
+        SimulatedBackend {
+            devices: Mutex::new(HashMap::new())
+        }
+    }
+}
+
+#[async_trait]
+impl ExecutorBackend for SimulatedBackend {
+    async fn execute(&self, device: &Device, action: &CommandAction, subject: &SubjectDefinition) -> Result<(), ExecutionError> {
+        let mut devices = self.devices
+            .lock()
+            .map_err(|_| ExecutionError::failed("Simulated home state is poisoned"))?;
+        // Levels stay within the subject's range, a new device starts at the bottom of it
+        let state = devices.entry(device.id.clone()).or_insert(SimulatedDevice { on: false, level: subject.min });
+
+        match action {
+            CommandAction::Switch(CommandSwitchValue::On) => state.on = true,
+            CommandAction::Switch(CommandSwitchValue::Off) => state.on = false,
+            CommandAction::Switch(CommandSwitchValue::Toggle) => state.on = !state.on,
+            CommandAction::Gradient(CommandGradientValue::Min) => state.level = subject.min,
+            CommandAction::Gradient(CommandGradientValue::Max) => state.level = subject.max,
+            CommandAction::Gradient(CommandGradientValue::More) => state.level = subject.clamp(state.level + subject.step),
+            CommandAction::Gradient(CommandGradientValue::Less) => state.level = subject.clamp(state.level - subject.step),
+            CommandAction::Gradient(CommandGradientValue::Set(level)) => state.level = subject.clamp(*level)
+        };
+
+        println!("Simulated '{}' is now {:?}", device.id, state);
+        Ok(())
+    }
+}
@@ -0,0 +1,11 @@
+use crate::model::{command_action::CommandAction, device::Device};
+
+/// Fills in the placeholders backends accept in payloads, URLs and arguments
+pub fn render(template: &str, device: &Device, action: &CommandAction) -> String {
+    template
+        .replace("{device}", &device.id)
+        .replace("{location}", &device.location)
+        .replace("{subject}", &device.subject.label)
+        .replace("{action}", action.label())
+        .replace("{value}", &action.value())
+}
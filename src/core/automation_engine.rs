@@ -0,0 +1,68 @@
+use std::{collections::{HashMap, HashSet}, sync::mpsc::Receiver, time::Instant};
+
+use crate::model::automation::Automation;
+
+use super::{commander::Commander, state_store::StateStore};
+
+// An automation whose condition holds, with when it started to
+struct Holding {
+    since: Instant,
+    has_fired: bool
+}
+
+/// Watches the state store for the automations from the command map. An
+/// automation fires once when its condition starts to hold (or held long
+/// enough) and not again until the condition stopped holding in between.
+pub struct AutomationEngine {
+    changes: Receiver<String>,
+    holding: HashMap<String, Holding>
+}
+
+impl AutomationEngine {
+    pub fn new(states: &StateStore) -> Self {
+        AutomationEngine {
+            changes: states.subscribe(),
+            holding: HashMap::new()
+        }
+    }
+
+    /// The automations that should fire now
+    pub fn take_triggered<'a>(&mut self, automations: &'a [Automation], commander: &Commander, states: &StateStore) -> Vec<&'a Automation> {
+        let changed: HashSet<String> = self.changes.try_iter().collect();
+        let now = Instant::now();
+        let mut triggered = Vec::new();
+
+        for automation in automations {
+            let trigger = &automation.trigger;
+            let devices = commander.devices_of(&trigger.location, &trigger.subject);
+
+            // Only a change to one of its devices can start or stop the condition holding
+            if devices.iter().any(|device| changed.contains(&device.id)) {
+                let since = devices
+                    .iter()
+                    .filter(|device| states.get(&device.id).is_some_and(|state| trigger.condition.holds(&state)))
+                    .filter_map(|device| states.changed_at(&device.id))
+                    .min();
+
+                match since {
+                    Some(since) => {
+                        self.holding.entry(automation.name.clone()).or_insert(Holding { since, has_fired: false });
+                    }
+                    None => {
+                        self.holding.remove(&automation.name);
+                    }
+                };
+            }
+
+            if let Some(holding) = self.holding.get_mut(&automation.name) {
+                let has_held_long_enough = now.duration_since(holding.since) >= trigger.duration.unwrap_or_default();
+                if !holding.has_fired && has_held_long_enough {
+                    holding.has_fired = true;
+                    triggered.push(automation);
+                }
+            }
+        }
+
+        triggered
+    }
+}
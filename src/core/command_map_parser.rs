@@ -0,0 +1,687 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::{errors::command_map_error::CommandMapError, model::{aliases::Aliases, audio_source::AudioSource, automation::{Automation, StateCondition, Trigger}, backend_config::{BackendConfig, LOG_BACKEND}, command::Command, command_action::{ActionKind, CommandAction, CommandGradientValue, CommandSwitchValue}, command_map::CommandMap, command_subject::SubjectDefinition, device::{Device, DeviceAddress}, device_state::switch_state, routine::Routine, scene::{LocationGroup, Scene, EVERYWHERE, EVERYWHERE_ALIASES}}, traits::labelable::Labelable};
+
+use super::{recurrence::parse_recurrence, spoken_time::parse_duration, yaml_position::yaml_position};
+
+// Bump whenever the layout of command_map.yaml changes in an incompatible way
+pub const COMMAND_MAP_VERSION: u32 = 1;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CommandMapSchema {
+    version: Option<u32>,
+    locations: BTreeMap<String, LocationSchema>,
+    #[serde(default)]
+    subjects: BTreeMap<String, SubjectSchema>,
+    #[serde(default)]
+    actions: BTreeMap<ActionName, AliasesSchema>,
+    #[serde(default)]
+    sources: BTreeMap<String, String>,
+    #[serde(default)]
+    devices: BTreeMap<String, DeviceSchema>,
+    #[serde(default)]
+    backends: BTreeMap<String, BackendConfig>,
+    #[serde(default)]
+    groups: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    scenes: BTreeMap<String, Vec<SceneCommandSchema>>,
+    #[serde(default)]
+    routines: BTreeMap<String, RoutineSchema>,
+    #[serde(default)]
+    automations: BTreeMap<String, AutomationSchema>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LocationSchema {
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    switch: Vec<String>,
+    #[serde(default)]
+    gradient: Vec<String>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SubjectSchema {
+    name: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+    actions: Option<Vec<ActionKind>>,
+    step: Option<i32>,
+    min: Option<i32>,
+    max: Option<i32>,
+    unit: Option<String>
+}
+
+// Only one of the addressing fields is expected, which one depends on the backend
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeviceSchema {
+    location: String,
+    subject: String,
+    backend: String,
+    topic: Option<String>,
+    url: Option<String>,
+    entity_id: Option<String>,
+    state_topic: Option<String>
+}
+
+// Either an `action` or a `value` to set the subject to
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneCommandSchema {
+    location: String,
+    subject: String,
+    action: Option<ActionName>,
+    value: Option<i32>
+}
+
+// `when` is either a crontab line or said out loud, e.g. "every weekday at 6:45"
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RoutineSchema {
+    when: String,
+    announcement: Option<String>,
+    commands: Vec<SceneCommandSchema>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AutomationSchema {
+    when: TriggerSchema,
+    then: Vec<SceneCommandSchema>
+}
+
+// Exactly one of `is`, `below` or `above`. `for` is a duration like "10 minutes".
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TriggerSchema {
+    location: String,
+    subject: String,
+    is: Option<String>,
+    below: Option<i32>,
+    above: Option<i32>,
+    #[serde(rename = "for")]
+    duration: Option<String>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AliasesSchema {
+    aliases: Vec<String>
+}
+
+#[derive(Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "String")]
+struct ActionName(String);
+
+impl TryFrom<String> for ActionName {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let labels = CommandAction::labels();
+        if labels.contains(&value) {
+            Ok(ActionName(value))
+        } else {
+            Err(format!("unknown action `{}`, expected one of {}", value, quoted(&labels)))
+        }
+    }
+}
+
+fn quoted(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|value| format!("`{}`", value))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// Where the command map came from, so problems found after deserializing
+// can still point at the line they're on
+struct MapFile<'a> {
+    path: &'a str,
+    contents: &'a str
+}
+
+impl MapFile<'_> {
+    fn error(&self, at: &[&str], message: String) -> CommandMapError {
+        CommandMapError::at(self.path, yaml_position(self.contents, at), message)
+    }
+}
+
+// What scenes, routines and automations are checked against
+struct Declared<'a> {
+    commands: &'a [Command],
+    subjects: &'a [SubjectDefinition],
+    locations: &'a [String],
+    groups: &'a [LocationGroup]
+}
+
+pub fn parse_command_map(file_path: &str) -> Result<(CommandMap, Vec<String>), CommandMapError> {
+    let contents = std::fs::read_to_string(file_path)
+        .map_err(|e| CommandMapError::new(file_path, e.to_string()))?;
+    let file = MapFile { path: file_path, contents: &contents };
+
+    let schema: CommandMapSchema = match serde_yaml::from_str(&contents) {
+        Ok(schema) => schema,
+        Err(e) => {
+            // An outdated map gets a helpful error instead of complaints about every field that moved
+            if let Ok(document) = serde_yaml::from_str::<serde_yaml::Value>(&contents) {
+                let version = document.get("version").and_then(serde_yaml::Value::as_u64).and_then(|version| u32::try_from(version).ok());
+                check_version(&file, version)?;
+            }
+            return Err(CommandMapError::from_yaml(file_path, e));
+        }
+    };
+    check_version(&file, schema.version)?;
+
+    let subjects = build_subjects(&file, &schema.subjects)?;
+
+    build_command_map(&file, schema, subjects)
+}
+
+fn check_version(file: &MapFile, version: Option<u32>) -> Result<(), CommandMapError> {
+    match version {
+        Some(COMMAND_MAP_VERSION) => Ok(()),
+        Some(version) => Err(file.error(
+            &["version"],
+            format!("unsupported version {}, expected {}", version, COMMAND_MAP_VERSION)
+        )),
+        None => Err(file.error(
+            &[],
+            format!("missing `version`, the current command map version is {}", COMMAND_MAP_VERSION)
+        ))
+    }
+}
+
+fn find_subject<'a>(file: &MapFile, at: &[&str], subjects: &'a [SubjectDefinition], label: &str) -> Result<&'a SubjectDefinition, CommandMapError> {
+    subjects.iter().find(|definition| definition.label == label).ok_or_else(|| {
+        let labels: Vec<String> = subjects.iter().map(|definition| definition.label.clone()).collect();
+        file.error(at, format!("unknown subject `{}`, expected one of {}", label, quoted(&labels)))
+    })
+}
+
+// The built in subjects can be tweaked and new ones added
+fn build_subjects(file: &MapFile, schema: &BTreeMap<String, SubjectSchema>) -> Result<Vec<SubjectDefinition>, CommandMapError> {
+    let mut subjects = SubjectDefinition::defaults();
+
+    for (label, entry) in schema {
+        let index = match subjects.iter().position(|subject| subject.label == *label) {
+            Some(index) => index,
+            None => {
+                if entry.actions.is_none() {
+                    return Err(file.error(
+                        &["subjects", label],
+                        format!("subject `{}` is missing the list of supported `actions`", label)
+                    ));
+                }
+
+                subjects.push(SubjectDefinition::new(label, label, &[]));
+                subjects.len() - 1
+            }
+        };
+
+        let subject = &mut subjects[index];
+        if let Some(name) = &entry.name {
+            subject.name = name.clone();
+        }
+        if let Some(actions) = &entry.actions {
+            subject.actions = actions.clone();
+        }
+        if let Some(unit) = &entry.unit {
+            subject.unit = unit.clone();
+        }
+        subject.step = entry.step.unwrap_or(subject.step);
+        subject.min = entry.min.unwrap_or(subject.min);
+        subject.max = entry.max.unwrap_or(subject.max);
+        subject.aliases.extend(entry.aliases.iter().cloned());
+
+        if subject.min > subject.max || subject.step <= 0 {
+            return Err(file.error(
+                &["subjects", label],
+                format!("subject `{}` needs a positive `step` and `min` can't be above `max`", label)
+            ));
+        }
+    }
+
+    Ok(subjects)
+}
+
+fn build_command_map(file: &MapFile, schema: CommandMapSchema, subjects: Vec<SubjectDefinition>) -> Result<(CommandMap, Vec<String>), CommandMapError> {
+    let mut locations = Vec::new();
+    let mut commands = Vec::new();
+    let mut aliases = Aliases::default();
+
+    for (location, entry) in schema.locations {
+        if location == EVERYWHERE {
+            return Err(file.error(
+                &["locations", &location],
+                format!("`{}` can't be used as a location, it already stands for all of them", EVERYWHERE)
+            ));
+        }
+
+        for alias in &entry.aliases {
+            aliases.locations.add(&location, alias);
+        }
+
+        let switches = entry.switch
+            .iter()
+            .enumerate()
+            .map(|(index, subject)| (CommandAction::Switch(CommandSwitchValue::Off), "switch", index, subject));
+        let gradients = entry.gradient
+            .iter()
+            .enumerate()
+            .map(|(index, subject)| (CommandAction::Gradient(CommandGradientValue::Min), "gradient", index, subject));
+
+        for (action, kind, index, subject) in switches.chain(gradients) {
+            let at = ["locations", &location, kind, &index.to_string()];
+            let definition = find_subject(file, &at, &subjects, subject)?;
+
+            if !definition.supports(action.kind()) {
+                return Err(file.error(
+                    &at,
+                    format!("location `{}` lists `{}` under `{}` which it doesn't support", location, subject, action.kind())
+                ));
+            }
+
+            commands.push(Command {
+                location: location.clone(),
+                action,
+                subject: definition.subject()
+            });
+        }
+
+        locations.push(location);
+    }
+
+    for alias in EVERYWHERE_ALIASES {
+        aliases.locations.add(EVERYWHERE, alias);
+    }
+
+    for subject in &subjects {
+        aliases.locations.add(EVERYWHERE, &format!("all the {}", subject.name));
+        for alias in &subject.aliases {
+            aliases.subjects.add(&subject.label, alias);
+        }
+    }
+
+    for (action, entry) in &schema.actions {
+        for alias in &entry.aliases {
+            aliases.actions.add(&action.0, alias);
+        }
+    }
+
+    let mut sources = Vec::new();
+    for (name, location) in schema.sources {
+        if !locations.contains(&location) {
+            return Err(file.error(
+                &["sources", &name],
+                format!("source `{}` refers to unknown location `{}`", name, location)
+            ));
+        }
+
+        sources.push(AudioSource { name, location });
+    }
+
+    let mut groups = Vec::new();
+    for (name, members) in schema.groups {
+        if locations.contains(&name) || name == EVERYWHERE {
+            return Err(file.error(
+                &["groups", &name],
+                format!("group `{}` has the same name as a location", name)
+            ));
+        }
+
+        if let Some(index) = members.iter().position(|member| !locations.contains(member)) {
+            return Err(file.error(
+                &["groups", &name, &index.to_string()],
+                format!("group `{}` refers to unknown location `{}`", name, members[index])
+            ));
+        }
+
+        if members.is_empty() {
+            return Err(file.error(&["groups", &name], format!("group `{}` has no locations", name)));
+        }
+
+        groups.push(LocationGroup { name, locations: members });
+    }
+
+    let declared = Declared { commands: &commands, subjects: &subjects, locations: &locations, groups: &groups };
+
+    let mut scenes = Vec::new();
+    for (name, entries) in schema.scenes {
+        let scene_commands = entries
+            .into_iter()
+            .enumerate()
+            .map(|(index, entry)| scene_command(file, &["scenes", &name, &index.to_string()], &format!("scene `{}`", name), entry, &declared))
+            .collect::<Result<Vec<Command>, CommandMapError>>()?;
+
+        scenes.push(Scene { name, commands: scene_commands });
+    }
+
+    let mut routines = Vec::new();
+    for (name, entry) in schema.routines {
+        let recurrence = parse_recurrence(&entry.when)
+            .map_err(|e| file.error(&["routines", &name, "when"], format!("routine `{}` has an invalid `when`: {}", name, e)))?;
+        let routine_commands = entry.commands
+            .into_iter()
+            .enumerate()
+            .map(|(index, command)| {
+                scene_command(file, &["routines", &name, "commands", &index.to_string()], &format!("routine `{}`", name), command, &declared)
+            })
+            .collect::<Result<Vec<Command>, CommandMapError>>()?;
+
+        routines.push(Routine { name, recurrence, commands: routine_commands, announcement: entry.announcement });
+    }
+
+    let mut automations = Vec::new();
+    for (name, entry) in schema.automations {
+        let trigger = build_trigger(file, &name, entry.when, &declared)?;
+        let automation_commands = entry.then
+            .into_iter()
+            .enumerate()
+            .map(|(index, command)| {
+                scene_command(file, &["automations", &name, "then", &index.to_string()], &format!("automation `{}`", name), command, &declared)
+            })
+            .collect::<Result<Vec<Command>, CommandMapError>>()?;
+
+        automations.push(Automation { name, trigger, commands: automation_commands });
+    }
+
+    let mut devices = Vec::new();
+    for (id, entry) in schema.devices {
+        let subject = find_subject(file, &["devices", &id, "subject"], &subjects, &entry.subject)?.subject();
+
+        if !commands.iter().any(|command| command.location == entry.location && command.subject == subject) {
+            return Err(file.error(
+                &["devices", &id],
+                format!("device `{}` is a `{}` in `{}` but the location doesn't list it", id, subject.label, entry.location)
+            ));
+        }
+
+        let address_field = match schema.backends.get(&entry.backend) {
+            Some(backend) => backend.address_field(),
+            None if entry.backend == LOG_BACKEND => None,
+            None => return Err(file.error(
+                &["devices", &id, "backend"],
+                format!("device `{}` uses the backend `{}` which isn't declared in `backends`", id, entry.backend)
+            ))
+        };
+
+        let address = match (entry.topic, entry.url, entry.entity_id) {
+            (None, None, None) => DeviceAddress::None,
+            (Some(topic), None, None) => DeviceAddress::Topic(topic),
+            (None, Some(url), None) => DeviceAddress::Url(url),
+            (None, None, Some(entity_id)) => DeviceAddress::EntityId(entity_id),
+            _ => return Err(file.error(
+                &["devices", &id],
+                format!("device `{}` can only have one of `topic`, `url` or `entity_id`", id)
+            ))
+        };
+
+        let has_address = matches!(
+            (&address, address_field),
+            (_, None)
+                | (DeviceAddress::Topic(_), Some("topic"))
+                | (DeviceAddress::Url(_), Some("url"))
+                | (DeviceAddress::EntityId(_), Some("entity_id"))
+        );
+        if !has_address {
+            return Err(file.error(
+                &["devices", &id],
+                format!("device `{}` needs a `{}` for the backend `{}`", id, address_field.unwrap_or_default(), entry.backend)
+            ));
+        }
+
+        if entry.state_topic.is_some() && !matches!(schema.backends.get(&entry.backend), Some(BackendConfig::Mqtt(_))) {
+            return Err(file.error(
+                &["devices", &id, "state_topic"],
+                format!("device `{}` has a `state_topic` but the backend `{}` isn't an MQTT backend", id, entry.backend)
+            ));
+        }
+
+        devices.push(Device {
+            id,
+            location: entry.location,
+            subject,
+            backend: entry.backend,
+            address,
+            state_topic: entry.state_topic
+        });
+    }
+
+    // An automation only notices changes of its devices. Imported Home Assistant
+    // devices aren't known yet, those are checked once they're imported.
+    let imports_devices = schema.backends
+        .values()
+        .any(|backend| matches!(backend, BackendConfig::HomeAssistant(config) if config.import));
+    if !imports_devices {
+        for automation in &automations {
+            let trigger = &automation.trigger;
+            let targets = target_locations(&trigger.location, &locations, &groups).unwrap_or_default();
+
+            if !devices.iter().any(|device| targets.contains(&device.location) && device.subject == trigger.subject) {
+                return Err(file.error(
+                    &["automations", &automation.name, "when"],
+                    format!("automation `{}` waits for the `{}` in `{}` but there's no such device in `devices`", automation.name, trigger.subject.label, trigger.location)
+                ));
+            }
+        }
+    }
+
+    let backends = schema.backends;
+
+    Ok((CommandMap { commands, subjects, devices, backends, sources, groups, scenes, routines, automations, aliases }, locations))
+}
+
+// A command in a scene or routine, which can address a whole group as long as
+// one of its locations has the subject
+fn scene_command(file: &MapFile, at: &[&str], owner: &str, entry: SceneCommandSchema, declared: &Declared) -> Result<Command, CommandMapError> {
+    let action = match (entry.action, entry.value) {
+        (Some(action), None) => CommandAction::from_label(&action.0),
+        (None, Some(value)) => CommandAction::Gradient(CommandGradientValue::Set(value)),
+        _ => return Err(file.error(
+            at,
+            format!("{} needs either an `action` or a `value` for the `{}` in `{}`", owner, entry.subject, entry.location)
+        ))
+    };
+
+    let Some(targets) = target_locations(&entry.location, declared.locations, declared.groups) else {
+        return Err(file.error(
+            &[at, &["location"]].concat(),
+            format!("{} refers to unknown location `{}`", owner, entry.location)
+        ));
+    };
+
+    let subject = find_subject(file, &[at, &["subject"]].concat(), declared.subjects, &entry.subject)?.subject();
+
+    let is_listed = declared.commands.iter().any(|command| {
+        targets.contains(&command.location) && command.subject == subject && command.action.is_same_action(&action)
+    });
+    if !is_listed {
+        return Err(file.error(
+            at,
+            format!("{} uses the `{}` in `{}` as a `{}` but the location doesn't list it", owner, subject.label, entry.location, action.kind())
+        ));
+    }
+
+    Ok(Command { location: entry.location, action, subject })
+}
+
+// The condition has to be about a device state the location can have,
+// only gradient subjects have a level to compare
+fn build_trigger(file: &MapFile, name: &str, entry: TriggerSchema, declared: &Declared) -> Result<Trigger, CommandMapError> {
+    let at = ["automations", name, "when"];
+
+    let condition = match (entry.is, entry.below, entry.above) {
+        (Some(state), None, None) => match switch_state(&state) {
+            Some(on) => StateCondition::Is(on),
+            None => return Err(file.error(
+                &[&at[..], &["is"]].concat(),
+                format!("automation `{}` waits for the state `{}`, expected `on` or `off`", name, state)
+            ))
+        },
+        (None, Some(level), None) => StateCondition::Below(level),
+        (None, None, Some(level)) => StateCondition::Above(level),
+        _ => return Err(file.error(
+            &at,
+            format!("automation `{}` needs exactly one of `is`, `below` or `above` in `when`", name)
+        ))
+    };
+
+    let duration = match entry.duration {
+        Some(duration) => match parse_duration(&duration) {
+            Some(duration) => Some(duration),
+            None => return Err(file.error(
+                &[&at[..], &["for"]].concat(),
+                format!("automation `{}` has `for: {}` which isn't a duration", name, duration)
+            ))
+        },
+        None => None
+    };
+
+    let Some(targets) = target_locations(&entry.location, declared.locations, declared.groups) else {
+        return Err(file.error(
+            &[&at[..], &["location"]].concat(),
+            format!("automation `{}` refers to unknown location `{}`", name, entry.location)
+        ));
+    };
+
+    let subject = find_subject(file, &[&at[..], &["subject"]].concat(), declared.subjects, &entry.subject)?.subject();
+
+    let needs_level = !matches!(condition, StateCondition::Is(_));
+    let is_listed = declared.commands.iter().any(|command| {
+        targets.contains(&command.location) && command.subject == subject && (!needs_level || command.action.kind() == ActionKind::Gradient)
+    });
+    if !is_listed {
+        return Err(file.error(
+            &at,
+            format!("automation `{}` waits for the `{}` in `{}` but the location doesn't list it{}", name, subject.label, entry.location, if needs_level { " as a `gradient`" } else { "" })
+        ));
+    }
+
+    Ok(Trigger { location: entry.location, subject, condition, duration })
+}
+
+// The locations a scene, routine or automation addresses, which can be a whole group
+fn target_locations(location: &str, locations: &[String], groups: &[LocationGroup]) -> Option<Vec<String>> {
+    match groups.iter().find(|group| group.name == location) {
+        Some(group) => Some(group.locations.clone()),
+        None if location == EVERYWHERE => Some(locations.to_vec()),
+        None if locations.iter().any(|known| known == location) => Some(vec![location.to_string()]),
+        None => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCATIONS: &str = "\
+version: 1
+locations:
+  kitchen:
+    switch:
+      - light
+    gradient:
+      - windowblinds
+";
+
+    // The error without the file name in front, e.g. "3:5: unknown subject ..."
+    fn parse(name: &str, contents: &str) -> Result<(CommandMap, Vec<String>), String> {
+        let file = std::env::temp_dir().join(format!("jarvis-test-{}-{}.yaml", name, std::process::id()));
+        std::fs::write(&file, contents).unwrap();
+        let path = file.to_string_lossy().to_string();
+
+        let parsed = parse_command_map(&path).map_err(|e| e.to_string().trim_start_matches(&format!("{}:", path)).trim().to_string());
+        let _ = std::fs::remove_file(&file);
+        parsed
+    }
+
+    #[test]
+    fn unknown_subjects_point_at_the_list_item() {
+        let contents = LOCATIONS.replace("      - light\n", "      - light\n      - toaster\n");
+        let error = parse("unknown-subject", &contents).unwrap_err();
+
+        assert_eq!(error, "6:9: unknown subject `toaster`, expected one of `light`, `teapot`, `windowblinds`, `temperature`, `ventilator`");
+    }
+
+    #[test]
+    fn an_outdated_map_is_told_about_its_version_first() {
+        // `switch` used to be a plain list of subjects per location
+        let contents = "version: 2\nlocations:\n  kitchen: [light]\n";
+
+        assert_eq!(parse("outdated", contents).unwrap_err(), "1:1: unsupported version 2, expected 1");
+        assert_eq!(
+            parse("unversioned", "locations: {}\n").unwrap_err(),
+            "1:1: missing `version`, the current command map version is 1"
+        );
+    }
+
+    #[test]
+    fn typos_keep_the_position_serde_found() {
+        let contents = LOCATIONS.replace("    gradient:", "    gradiant:");
+        let error = parse("typo", &contents).unwrap_err();
+
+        assert!(error.starts_with("6:5: locations.kitchen: unknown field `gradiant`"), "{}", error);
+    }
+
+    #[test]
+    fn scene_and_routine_entries_are_checked_where_they_are() {
+        let scene = "scenes:\n  movie night:\n    - location: kitchen\n      subject: light\n";
+        assert_eq!(
+            parse("scene", &format!("{}{}", LOCATIONS, scene)).unwrap_err(),
+            "10:7: scene `movie night` needs either an `action` or a `value` for the `light` in `kitchen`"
+        );
+
+        let routine = "routines:\n  wake up:\n    when: every day\n    commands: []\n";
+        assert_eq!(
+            parse("routine", &format!("{}{}", LOCATIONS, routine)).unwrap_err(),
+            "10:5: routine `wake up` has an invalid `when`: `every day` needs a time of day, e.g. `every weekday at 6:45`"
+        );
+    }
+
+    #[test]
+    fn aliases_and_groups_lead_to_the_declared_locations() {
+        let contents = format!("{}{}", LOCATIONS.replace("  kitchen:\n", "  kitchen:\n    aliases:\n      - cookhouse\n"), "groups:\n  downstairs:\n    - kitchen\n");
+        let (map, locations) = parse("aliases", &contents).unwrap();
+
+        assert_eq!(locations, ["kitchen"]);
+        assert_eq!(map.aliases.locations.canonical("cookhouse"), "kitchen");
+        assert_eq!(map.aliases.locations.canonical("all the light"), EVERYWHERE);
+        assert_eq!(map.groups[0].locations, ["kitchen"]);
+    }
+
+    #[test]
+    fn an_automation_needs_a_device_to_watch() {
+        let automation = "\
+automations:
+  fan:
+    when:
+      location: kitchen
+      subject: light
+      is: on
+    then:
+      - location: kitchen
+        subject: light
+        action: turn off
+";
+        let error = parse("automation", &format!("{}{}", LOCATIONS, automation)).unwrap_err();
+        assert_eq!(error, "10:5: automation `fan` waits for the `light` in `kitchen` but there's no such device in `devices`");
+
+        let devices = "\
+devices:
+  kitchen-light:
+    location: kitchen
+    subject: light
+    backend: log
+";
+        let (map, _) = parse("automation-device", &format!("{}{}{}", LOCATIONS, automation, devices)).unwrap();
+        assert_eq!(map.automations[0].trigger.subject.label, "light");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local, TimeDelta};
+
+use crate::model::schedule::{ScheduleOutcome, ScheduleRequest, ScheduledCommand};
+
+use super::persisted::{load_json, save_json};
+
+// Commands that were due while Jarvis was off are still run if they're not
+// too late, turning on the light for a morning that's long gone helps no one
+const MISSED_GRACE_PERIOD: TimeDelta = TimeDelta::minutes(5);
+
+/// Keeps track of commands that should run later. Every change is written
+/// to disk straight away so nothing is lost when Jarvis restarts.
+pub struct CommandScheduler {
+    file: PathBuf,
+    pending: Vec<ScheduledCommand>
+}
+
+impl CommandScheduler {
+    /// Picks up the commands scheduled before the last restart. A broken
+    /// file is logged and replaced, we'd rather lose a schedule than not start.
+    pub fn load(file: PathBuf) -> Self {
+        let pending: Vec<ScheduledCommand> = load_json(&file, "scheduled commands");
+
+        if !pending.is_empty() {
+            println!("Loaded {} scheduled commands from {}", pending.len(), file.display());
+        }
+
+        CommandScheduler { file, pending }
+    }
+
+    pub fn handle(&mut self, request: ScheduleRequest) -> ScheduleOutcome {
+        match request {
+            ScheduleRequest::Add { command, due } => {
+                let id = self.pending.iter().map(|scheduled| scheduled.id).max().unwrap_or(0) + 1;
+                let scheduled = ScheduledCommand { id, due, command };
+
+                self.pending.push(scheduled.clone());
+                self.pending.sort_by_key(|scheduled| scheduled.due);
+                self.save();
+
+                println!("Scheduled {:?} for {}", scheduled.command, scheduled.due);
+                ScheduleOutcome::Added(scheduled)
+            }
+            ScheduleRequest::List => ScheduleOutcome::Listed(self.pending.clone()),
+            ScheduleRequest::Cancel { location, subject } => {
+                let (cancelled, pending) = self.pending.drain(..).partition(|scheduled| {
+                    location.as_ref().is_none_or(|location| *location == scheduled.command.location) &&
+                    subject.as_ref().is_none_or(|subject| *subject == scheduled.command.subject)
+                });
+                self.pending = pending;
+                self.save();
+
+                ScheduleOutcome::Cancelled(cancelled)
+            }
+        }
+    }
+
+    /// Removes and returns the commands that are due
+    pub fn take_due(&mut self, now: DateTime<Local>) -> Vec<ScheduledCommand> {
+        if self.pending.iter().all(|scheduled| scheduled.due > now) {
+            return Vec::new();
+        }
+
+        let (due, pending): (Vec<ScheduledCommand>, Vec<ScheduledCommand>) = self.pending
+            .drain(..)
+            .partition(|scheduled| scheduled.due <= now);
+        self.pending = pending;
+        self.save();
+
+        due.into_iter()
+            .filter(|scheduled| {
+                let is_missed = now - scheduled.due > MISSED_GRACE_PERIOD;
+                if is_missed {
+                    println!("Skipping {:?}, it was due at {}", scheduled.command, scheduled.due);
+                }
+                !is_missed
+            })
+            .collect()
+    }
+
+    fn save(&self) {
+        save_json(&self.file, "scheduled commands", &self.pending);
+    }
+}
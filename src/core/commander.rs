@@ -1,34 +1,102 @@
 
-use std::{fs::File, io::{BufRead, BufReader}, path::PathBuf};
+use std::collections::BTreeMap;
 
-use anyhow::{Ok, Result};
-
-use crate::model::{command::Command, command_action::CommandAction, command_map::CommandMap, command_subject::CommandSubject};
+use crate::{model::{aliases::Aliases, audio_source::AudioSource, automation::Automation, backend_config::BackendConfig, clarification::{CommandSlot, PartialCommand, SlotOption}, command::Command, command_action::CommandAction, command_map::CommandMap, command_subject::{CommandSubject, SubjectDefinition}, device::Device, local_answer::Capabilities, routine::Routine, scene::{LocationGroup, Scene, EVERYWHERE}}, traits::labelable::Labelable};
 
 pub struct Commander {
     pub commands: Vec<Command>,
-    pub locations: Vec<String>
+    pub locations: Vec<String>,
+    pub subjects: Vec<SubjectDefinition>,
+    pub devices: Vec<Device>,
+    pub backends: BTreeMap<String, BackendConfig>,
+    pub sources: Vec<AudioSource>,
+    pub groups: Vec<LocationGroup>,
+    pub scenes: Vec<Scene>,
+    pub routines: Vec<Routine>,
+    pub automations: Vec<Automation>,
+    pub aliases: Aliases
 }
 
 impl Commander {
-    pub fn new() -> Self {
-        let file = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-            .join("config")
-            .join("command_map.yaml")
-            .to_str()
-            .expect("Could not construct the command_map.yaml path")
-            .to_owned();
-
-        let (map, locations) = parse_command_map(file)
-            .expect("Invalid command_map.yaml structure");
-
+    pub fn new(map: CommandMap, locations: Vec<String>) -> Self {
         Commander {
             commands: map.commands,
-            locations
+            locations,
+            subjects: map.subjects,
+            devices: map.devices,
+            backends: map.backends,
+            sources: map.sources,
+            groups: map.groups,
+            scenes: map.scenes,
+            routines: map.routines,
+            automations: map.automations,
+            aliases: map.aliases
         }
     }
 
+    pub fn subject(&self, label: &str) -> Option<CommandSubject> {
+        self.subjects
+            .iter()
+            .find(|definition| definition.label == label)
+            .map(SubjectDefinition::subject)
+    }
+
+    pub fn subject_definition(&self, subject: &CommandSubject) -> Option<&SubjectDefinition> {
+        self.subjects
+            .iter()
+            .find(|definition| definition.label == subject.label)
+    }
+
+    pub fn subject_labels(&self) -> Vec<String> {
+        self.subjects
+            .iter()
+            .map(|definition| definition.label.clone())
+            .collect()
+    }
+
+    /// The location an audio source is placed in, used when
+    /// the user doesn't name a room
+    pub fn home_location(&self, source: &str) -> Option<&String> {
+        self.sources
+            .iter()
+            .find(|audio_source| audio_source.name == source)
+            .map(|audio_source| &audio_source.location)
+    }
+
+    pub fn scene(&self, name: &str) -> Option<&Scene> {
+        self.scenes.iter().find(|scene| scene.name == name)
+    }
+
+    /// The group with the given name. `everywhere` is a group of all locations.
+    pub fn group(&self, name: &str) -> Option<LocationGroup> {
+        if name == EVERYWHERE {
+            return Some(LocationGroup { name: name.to_string(), locations: self.locations.clone() });
+        }
+
+        self.groups.iter().find(|group| group.name == name).cloned()
+    }
+
+    /// Splits a command for a group into a command for each of its locations
+    /// that can do it. Any other command is left as it is.
+    pub fn expand(&self, command: Command) -> Vec<Command> {
+        let Some(group) = self.group(&command.location) else {
+            return vec![command];
+        };
+
+        group.locations
+            .iter()
+            .map(|location| Command { location: location.clone(), ..command.clone() })
+            .filter(|command| self.supports_command(command))
+            .collect()
+    }
+
     pub fn supports_command(&self, command: &Command) -> bool {
+        if let Some(group) = self.group(&command.location) {
+            return group.locations
+                .iter()
+                .any(|location| self.supports_command(&Command { location: location.clone(), ..command.clone() }));
+        }
+
         for supported_command in &self.commands {
             if 
                 supported_command.location == command.location &&
@@ -41,89 +109,110 @@ impl Commander {
         
         false
     }
-}
-
-// A very crude parser for the command_map.yaml
-// I tried using serde_yaml but it was more pain than
-// benefit. This isn't great but gets the job done and 
-// is not the point of this project
-fn parse_command_map(file_path: String) -> Result<(CommandMap, Vec<String>)> {
-    let file = File::open(file_path)?;
-    let reader = BufReader::new(file);
 
-    let mut locations = Vec::new();
-    let mut commands = Vec::new();
+    /// The physical devices a command should drive. Empty if the
+    /// command map has no devices registered for it.
+    pub fn resolve(&self, command: &Command) -> Vec<Device> {
+        self.devices_of(&command.location, &command.subject)
+    }
 
-    let mut is_parsing_commands = false;
-    let mut current_location: Option<String> = None;
-    let mut current_action: Option<CommandAction> = None;
+    pub fn devices_of(&self, location: &str, subject: &CommandSubject) -> Vec<Device> {
+        let locations = match self.group(location) {
+            Some(group) => group.locations,
+            None => vec![location.to_string()]
+        };
 
-    for line in reader.lines().flatten() {
-        let (indentation, line) = cleaned_line_with_indentation(&line);
+        self.devices
+            .iter()
+            .filter(|device| locations.contains(&device.location) && device.subject == *subject)
+            .cloned()
+            .collect()
+    }
 
-        if !is_parsing_commands {
-            if line == "commands" {
-                is_parsing_commands = true;
+    /// Lists the options that could fill the missing slot of a partial
+    /// command so that the completed command would be supported.
+    pub fn options_for_slot(&self, slot: CommandSlot, pending: &PartialCommand) -> Vec<SlotOption> {
+        let matching = self.commands.iter().filter(|command| {
+            pending.location.as_ref().is_none_or(|location| *location == command.location) &&
+            pending.subject.as_ref().is_none_or(|subject| *subject == command.subject) &&
+            pending.action.is_none_or(|action| action.is_same_action(&command.action))
+        });
+
+        let mut options: Vec<SlotOption> = Vec::new();
+        for command in matching {
+            let candidates = match slot {
+                CommandSlot::Location => vec![(command.location.clone(), command.location.clone())],
+                CommandSlot::Subject => vec![(command.subject.label.clone(), command.subject.name.clone())],
+                CommandSlot::Action => CommandAction::labels()
+                    .into_iter()
+                    .filter(|label| CommandAction::from_label(label).is_same_action(&command.action))
+                    .map(|label| (label.clone(), label))
+                    .collect()
+            };
+
+            for (label, name) in candidates {
+                if !options.iter().any(|option| option.label == label) {
+                    options.push(SlotOption { label, name });
+                }
             }
-
-            continue;
         }
 
-        match indentation {
-            1 => { // one level in are locations
-                let location = line.to_string();
-                locations.push(location.clone());
-                current_location = Some(location);
-            }
-            2 => {
-                if let Some(action) = CommandAction::from_command_parser(line) {
-                    current_action = Some(action);
-                } else {
-                    current_action = None;
-                    continue;
-                }
-            }
-            3 => {
-                let subject = CommandSubject::from_command_parser(line);
-                let tuple = (current_location.clone(), current_action, subject);
-
-                if let (Some(location), Some(action), Some(subject)) = tuple {
-                    commands.push(Command { location, action, subject });
-                } else {
-                    continue;
-                }
-            }
-            _ => {
-                is_parsing_commands = false;
-                continue;
-            }
-        };
+        options
     }
 
-    Ok((CommandMap { commands }, locations))
-}
+    /// Fills a slot of a partial command from one of the options' label
+    pub fn fill_slot(&self, mut pending: PartialCommand, slot: CommandSlot, label: &str) -> PartialCommand {
+        match slot {
+            CommandSlot::Location => pending.location = Some(label.to_string()),
+            CommandSlot::Action => pending.action = Some(CommandAction::from_label(label)),
+            CommandSlot::Subject => pending.subject = self.subject(label)
+        };
 
-fn cleaned_line_with_indentation(line: &str) -> (usize, &str) {
-    const START_WHITESPACES: &[char; 4] = &[' ', '\t', '-', '"'];
-    const END_WHITESPACES: &[char; 2] = &['"', ':'];
+        pending
+    }
 
-    let mut spaces = 0;
-    let mut tabs = 0;
+    /// What can be controlled and where, to tell the user what Jarvis can do
+    pub fn capabilities(&self) -> Capabilities {
+        let mut subjects: Vec<String> = Vec::new();
+        for command in &self.commands {
+            if !subjects.contains(&command.subject.name) {
+                subjects.push(command.subject.name.clone());
+            }
+        }
 
-    for c in line.chars() {
-        match c {
-            ' ' => spaces += 1,
-            '\t' => tabs += 1,
-            _ => break
-        };
+        Capabilities {
+            subjects,
+            locations: self.locations.clone(),
+            scenes: self.scenes.iter().map(|scene| scene.name.clone()).collect()
+        }
     }
 
-    let space_indent = spaces / 2;
-    let tab_indent = tabs;
-
-    let cleaned = line
-        .trim_start_matches(START_WHITESPACES)
-        .trim_end_matches(END_WHITESPACES);
+    /// Every name the user might say to us. Used to nudge speech
+    /// recognition towards the words we actually understand.
+    pub fn vocabulary(&self) -> Vec<String> {
+        let subjects = self.subjects
+            .iter()
+            .map(|definition| definition.name.clone());
+
+        let mut vocabulary: Vec<String> = Vec::new();
+        let words = self.locations.iter().cloned()
+            .chain(self.aliases.locations.aliases().cloned())
+            .chain([EVERYWHERE.to_string()])
+            .chain(self.groups.iter().map(|group| group.name.clone()))
+            .chain(self.scenes.iter().map(|scene| scene.name.clone()))
+            .chain(self.routines.iter().map(|routine| routine.name.clone()))
+            .chain(["routine".to_string()])
+            .chain(CommandAction::labels())
+            .chain(self.aliases.actions.aliases().cloned())
+            .chain(subjects)
+            .chain(self.aliases.subjects.aliases().cloned());
+
+        for word in words {
+            if !vocabulary.contains(&word) {
+                vocabulary.push(word);
+            }
+        }
 
-    (space_indent + tab_indent, cleaned)
-}
\ No newline at end of file
+        vocabulary
+    }
+}
@@ -0,0 +1,190 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+const CONFIG_FLAG: &str = "--config";
+const CONFIG_ENV: &str = "JARVIS_CONFIG";
+const SYSTEM_CONFIG_DIR: &str = "/etc/jarvis";
+const SYSTEM_DATA_DIR: &str = "/var/lib/jarvis";
+
+const COMMAND_MAP_FILE: &str = "command_map.yaml";
+const SETTINGS_FILE: &str = "settings.yaml";
+const WHISPER_MODEL_FILE: &str = "ggml-model-whisper-tiny.en.bin";
+
+/// Figures out where the configuration files live. A binary copied to
+/// another machine can't rely on the directory it was built in so we look
+/// at the --config flag, the JARVIS_CONFIG env variable, the XDG config
+/// directory and /etc/jarvis in that order. The repository directories are
+/// only used as a last resort to keep `cargo run` working.
+#[derive(Debug)]
+pub struct ConfigPaths {
+    command_map: Option<PathBuf>,
+    search_dirs: Vec<PathBuf>
+}
+
+impl ConfigPaths {
+    pub fn from_args() -> Result<Self> {
+        let explicit = match config_flag(std::env::args().skip(1))? {
+            Some(path) => Some(path),
+            None => std::env::var_os(CONFIG_ENV).map(PathBuf::from)
+        };
+
+        Self::from_explicit(explicit)
+    }
+
+    fn from_explicit(explicit: Option<PathBuf>) -> Result<Self> {
+        let mut command_map = None;
+        let mut search_dirs = Vec::new();
+
+        // The config can point either to a directory or to the command map itself.
+        // A typo shouldn't quietly fall back to another config.
+        if let Some(path) = explicit {
+            if path.is_file() {
+                search_dirs.extend(path.parent().map(Path::to_path_buf));
+                command_map = Some(path);
+            } else if path.is_dir() {
+                search_dirs.push(path);
+            } else {
+                return Err(anyhow!("The configuration {} doesn't exist", path.display()));
+            }
+        }
+
+        search_dirs.extend(user_config_dir().map(|dir| dir.join("jarvis")));
+        search_dirs.push(PathBuf::from(SYSTEM_CONFIG_DIR));
+
+        Ok(ConfigPaths {
+            command_map,
+            search_dirs
+        })
+    }
+
+    pub fn command_map(&self) -> Result<PathBuf> {
+        if let Some(ref path) = self.command_map {
+            return Ok(path.clone());
+        }
+
+        self.find(COMMAND_MAP_FILE, development_dir().join("config").join(COMMAND_MAP_FILE))
+    }
+
+    /// Settings are optional, None if there's no settings file anywhere
+    pub fn settings(&self) -> Option<PathBuf> {
+        self.find(SETTINGS_FILE, development_dir().join("config").join(SETTINGS_FILE)).ok()
+    }
+
+    pub fn whisper_model(&self) -> Result<PathBuf> {
+        let file = PathBuf::from("models").join(WHISPER_MODEL_FILE);
+        self.find(&file, development_dir().join(&file))
+    }
+
+    /// Where Jarvis keeps what it has to remember between runs, e.g.
+    /// scheduled commands. Follows XDG_STATE_HOME like the config does.
+    pub fn data_dir(&self) -> PathBuf {
+        match std::env::var_os("XDG_STATE_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir).join("jarvis"),
+            _ => std::env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".local").join("state").join("jarvis"))
+                .unwrap_or_else(|| PathBuf::from(SYSTEM_DATA_DIR))
+        }
+    }
+
+    fn find(&self, file: impl AsRef<Path>, fallback: PathBuf) -> Result<PathBuf> {
+        let candidates: Vec<PathBuf> = self.search_dirs
+            .iter()
+            .map(|dir| dir.join(&file))
+            .chain(std::iter::once(fallback))
+            .collect();
+
+        candidates
+            .iter()
+            .find(|path| path.is_file())
+            .cloned()
+            .ok_or_else(|| anyhow!(
+                "Could not find {} in any of: {}",
+                file.as_ref().display(),
+                candidates.iter().map(|path| path.display().to_string()).collect::<Vec<String>>().join(", ")
+            ))
+    }
+}
+
+// Supports both `--config <path>` and `--config=<path>`
+fn config_flag(mut args: impl Iterator<Item = String>) -> Result<Option<PathBuf>> {
+    while let Some(arg) = args.next() {
+        if arg == CONFIG_FLAG {
+            return args
+                .next()
+                .map(|path| Some(PathBuf::from(path)))
+                .ok_or_else(|| anyhow!("{} requires a path", CONFIG_FLAG));
+        }
+
+        if let Some(path) = arg.strip_prefix(CONFIG_FLAG).and_then(|rest| rest.strip_prefix('=')) {
+            return Ok(Some(PathBuf::from(path)));
+        }
+    }
+
+    Ok(None)
+}
+
+fn user_config_dir() -> Option<PathBuf> {
+    match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+    }
+}
+
+fn development_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jarvis-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn flag(args: &[&str]) -> Result<Option<PathBuf>> {
+        config_flag(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn config_flag_takes_the_path_after_it_or_after_an_equals_sign() {
+        assert_eq!(flag(&["--verbose", "--config", "/srv/jarvis"]).unwrap(), Some(PathBuf::from("/srv/jarvis")));
+        assert_eq!(flag(&["--config=/srv/jarvis"]).unwrap(), Some(PathBuf::from("/srv/jarvis")));
+        assert_eq!(flag(&["--configure"]).unwrap(), None);
+        assert!(flag(&["--config"]).is_err());
+    }
+
+    #[test]
+    fn a_missing_explicit_config_stops_jarvis() {
+        let missing = config_dir("missing-config").join("typo");
+
+        let error = ConfigPaths::from_explicit(Some(missing.clone())).unwrap_err();
+        assert_eq!(error.to_string(), format!("The configuration {} doesn't exist", missing.display()));
+    }
+
+    #[test]
+    fn an_explicit_command_map_is_used_with_the_files_next_to_it() {
+        let dir = config_dir("explicit-map");
+        let map = dir.join("home.yaml");
+        std::fs::write(&map, "").unwrap();
+        std::fs::write(dir.join(SETTINGS_FILE), "").unwrap();
+
+        let paths = ConfigPaths::from_explicit(Some(map.clone())).unwrap();
+        assert_eq!(paths.command_map().unwrap(), map);
+        assert_eq!(paths.settings(), Some(dir.join(SETTINGS_FILE)));
+    }
+
+    #[test]
+    fn an_explicit_dir_is_searched_first() {
+        let dir = config_dir("explicit-dir");
+        std::fs::write(dir.join(COMMAND_MAP_FILE), "").unwrap();
+
+        let paths = ConfigPaths::from_explicit(Some(dir.clone())).unwrap();
+        assert_eq!(paths.command_map().unwrap(), dir.join(COMMAND_MAP_FILE));
+        assert!(paths.find("missing.yaml", dir.join("missing.yaml")).is_err());
+    }
+}
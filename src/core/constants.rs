@@ -1,2 +1,5 @@
 pub const MIC_SAMPLE_RATE: u32 = 48000;
-pub const AUDIO_SAMPLE_RATE: usize = 16000;
\ No newline at end of file
+pub const AUDIO_SAMPLE_RATE: usize = 16000;
+
+// Name of the microphone attached to this device, used to look up its home location
+pub const LOCAL_AUDIO_SOURCE: &str = "local";
\ No newline at end of file
@@ -0,0 +1,58 @@
+use std::time::{Duration, Instant};
+
+use crate::model::clarification::Clarification;
+
+// How long we wait for the user to answer a clarification question
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+enum DialogueState {
+    Idle,
+    AwaitingClarification {
+        clarification: Clarification,
+        asked_at: Instant
+    }
+}
+
+/// Keeps track of the conversation between the classifier and the user.
+/// When a command is missing a slot we ask about it and the next
+/// utterance is treated as the answer instead of a new instruction.
+pub struct Dialogue {
+    state: DialogueState
+}
+
+impl Dialogue {
+    pub fn new() -> Self {
+        Dialogue {
+            state: DialogueState::Idle
+        }
+    }
+
+    pub fn await_clarification(&mut self, clarification: Clarification) {
+        self.state = DialogueState::AwaitingClarification {
+            clarification,
+            asked_at: Instant::now()
+        };
+    }
+
+    /// Returns the pending clarification and resets the dialogue. Stale
+    /// questions are dropped so the utterance is treated as a new instruction.
+    pub fn take_pending(&mut self) -> Option<Clarification> {
+        match std::mem::replace(&mut self.state, DialogueState::Idle) {
+            DialogueState::AwaitingClarification { clarification, asked_at } if asked_at.elapsed() < REPLY_TIMEOUT => Some(clarification),
+            _ => None
+        }
+    }
+
+    /// Drops the pending question if the user didn't answer in time
+    pub fn expire(&mut self) {
+        let is_stale = match self.state {
+            DialogueState::AwaitingClarification { asked_at, .. } => asked_at.elapsed() >= REPLY_TIMEOUT,
+            DialogueState::Idle => false
+        };
+
+        if is_stale {
+            println!("Clarification question was not answered in time");
+            self.state = DialogueState::Idle;
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::{path::{Path, PathBuf}, time::SystemTime};
+
+/// Polls a file's modification time. Good enough for configuration
+/// files that change once in a blue moon.
+pub struct FileWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>
+}
+
+impl FileWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modification_time(&path);
+
+        FileWatcher {
+            path,
+            modified
+        }
+    }
+
+    /// Returns true once for every change of the file
+    pub fn has_changed(&mut self) -> bool {
+        let modified = modification_time(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+
+        self.modified = modified;
+        true
+    }
+}
+
+fn modification_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
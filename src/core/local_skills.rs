@@ -0,0 +1,64 @@
+use crate::model::{device_query::DeviceQuery, local_answer::LocalQuestion};
+
+use super::{commander::Commander, spoken_time::words};
+
+const QUESTION_WORDS: [&str; 4] = ["what", "what's", "which", "whats"];
+const CAPABILITY_PHRASES: [&str; 4] = ["what can you do", "what do you do", "what can i ask", "what can you help"];
+
+/// Recognizes the questions that have a definite answer, like the time or the
+/// state of a device. The generative model makes those up so it only gets
+/// the questions none of these match.
+pub fn recognize_question(question: &str, commander: &Commander) -> Option<LocalQuestion> {
+    let words = words(question);
+    let text = words.join(" ");
+    let mentions = |candidates: &[&str]| words.iter().any(|word| candidates.contains(&word.as_str()));
+    let asks_which = mentions(&QUESTION_WORDS);
+
+    if CAPABILITY_PHRASES.iter().any(|phrase| text.contains(phrase)) {
+        Some(LocalQuestion::Capabilities)
+    } else if mentions(&["timer", "timers"]) {
+        Some(LocalQuestion::Timers)
+    } else if mentions(&["uptime"]) || (mentions(&["long"]) && mentions(&["running", "up", "awake"])) {
+        Some(LocalQuestion::Uptime)
+    } else if mentions(&["date"]) {
+        Some(LocalQuestion::Date)
+    } else if asks_which && mentions(&["day"]) {
+        Some(LocalQuestion::Weekday)
+    } else if (asks_which || mentions(&["tell"])) && mentions(&["time"]) {
+        Some(LocalQuestion::Time)
+    } else {
+        device_query(&text, commander).map(LocalQuestion::DeviceState)
+    }
+}
+
+// "is the light in the bathroom on?" when it wasn't recognized as a status question
+fn device_query(text: &str, commander: &Commander) -> Option<DeviceQuery> {
+    let aliases = &commander.aliases;
+
+    let locations = commander.locations
+        .iter()
+        .map(|location| (location.clone(), location.clone()))
+        .chain(aliases.locations.aliases().map(|alias| (aliases.locations.canonical(alias).to_string(), alias.clone())));
+    let subjects = commander.subjects
+        .iter()
+        .flat_map(|definition| [(definition.label.clone(), definition.label.clone()), (definition.label.clone(), definition.name.clone())])
+        .chain(aliases.subjects.aliases().map(|alias| (aliases.subjects.canonical(alias).to_string(), alias.clone())));
+
+    let location = mentioned(text, locations)?;
+    let subject = commander.subject(&mentioned(text, subjects)?)?;
+
+    commander.commands
+        .iter()
+        .any(|command| command.location == location && command.subject == subject)
+        .then_some(DeviceQuery { location, subject })
+}
+
+// The canonical label of the longest name mentioned as whole words
+fn mentioned(text: &str, names: impl Iterator<Item = (String, String)>) -> Option<String> {
+    let text = format!(" {} ", text);
+
+    names
+        .filter(|(_, name)| text.contains(&format!(" {} ", name)))
+        .max_by_key(|(_, name)| name.len())
+        .map(|(label, _)| label)
+}
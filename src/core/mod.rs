@@ -1,4 +1,21 @@
 pub mod speech_recognizer;
 pub mod constants;
 pub mod jarvis_signals;
-pub mod commander;
\ No newline at end of file
+pub mod commander;
+pub mod command_map_parser;
+pub mod dialogue;
+pub mod config_paths;
+pub mod file_watcher;
+pub mod state_store;
+pub mod spoken_time;
+pub mod timer_scheduler;
+pub mod command_scheduler;
+pub mod recurrence;
+pub mod routine_runner;
+pub mod reminder_scheduler;
+pub mod automation_engine;
+pub mod local_skills;
+pub mod settings_parser;
+pub mod yaml_position;
+pub mod shared_commander;
+pub mod persisted;
\ No newline at end of file
@@ -0,0 +1,43 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Reads what `save_json` wrote, or the default if nothing was saved yet. A broken
+/// file is logged and ignored, we'd rather lose some state than not start.
+/// `what` names the contents in the logs, e.g. "reminders".
+pub fn load_json<T: DeserializeOwned + Default>(file: &Path, what: &str) -> T {
+    match read_json(file, what) {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("Could not read the {}: {:?}", what, e);
+            T::default()
+        }
+    }
+}
+
+/// Writes the state to disk straight away so nothing is lost when Jarvis restarts.
+/// Failing to is only logged, Jarvis keeps going with what it has in memory.
+pub fn save_json<T: Serialize + ?Sized>(file: &Path, what: &str, value: &T) {
+    if let Err(e) = write_json(file, value) {
+        eprintln!("Could not save the {}: {:?}", what, e);
+    }
+}
+
+fn read_json<T: DeserializeOwned + Default>(file: &Path, what: &str) -> Result<T> {
+    if !file.exists() {
+        return Ok(T::default());
+    }
+
+    let contents = std::fs::read_to_string(file).with_context(|| format!("Could not read {}", file.display()))?;
+    serde_json::from_str(&contents).with_context(|| format!("Invalid {} in {}", what, file.display()))
+}
+
+fn write_json<T: Serialize + ?Sized>(file: &Path, value: &T) -> Result<()> {
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Could not create {}", dir.display()))?;
+    }
+
+    let contents = serde_json::to_string_pretty(value)?;
+    std::fs::write(file, contents).with_context(|| format!("Could not write {}", file.display()))
+}
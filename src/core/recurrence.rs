@@ -0,0 +1,197 @@
+use chrono::Weekday;
+
+use crate::model::routine::Recurrence;
+
+use super::spoken_time::{clock_time, on_24_hour_clock, words};
+
+const WEEKDAYS: [Weekday; 7] = [Weekday::Sun, Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat];
+
+// Said in front of the days, e.g. "every monday and friday at 8"
+const DAY_FILLERS: [&str; 3] = ["every", "on", "and"];
+
+/// Reads when a routine should run, either as a crontab line ("45 6 * * 1-5")
+/// or the way it would be said ("every weekday at 6:45"). Times without am
+/// or pm are read as on a 24 hour clock.
+pub fn parse_recurrence(expression: &str) -> Result<Recurrence, String> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    let is_cron = fields.len() == 5 && fields
+        .iter()
+        .all(|field| field.chars().all(|c| c.is_ascii_digit() || "*,-/".contains(c)));
+
+    if is_cron {
+        parse_cron(&fields)
+    } else {
+        parse_spoken(expression)
+    }
+}
+
+fn parse_cron(fields: &[&str]) -> Result<Recurrence, String> {
+    // Sunday can be written as 0 or 7
+    let weekdays = cron_field(fields[4], 0, 7)?
+        .map(|days| {
+            let mut days: Vec<u32> = days.into_iter().map(|day| day % 7).collect();
+            days.sort();
+            days.dedup();
+            days
+        });
+
+    Ok(Recurrence {
+        minutes: cron_field(fields[0], 0, 59)?,
+        hours: cron_field(fields[1], 0, 23)?,
+        days_of_month: cron_field(fields[2], 1, 31)?,
+        months: cron_field(fields[3], 1, 12)?,
+        weekdays
+    })
+}
+
+// Supports lists, ranges and steps, e.g. "1-5", "0,30" or "*/15"
+fn cron_field(field: &str, min: u32, max: u32) -> Result<Option<Vec<u32>>, String> {
+    if field == "*" {
+        return Ok(None);
+    }
+
+    let number = |value: &str| match value.parse::<u32>() {
+        Ok(number) if (min..=max).contains(&number) => Ok(number),
+        _ => Err(format!("`{}` in `{}` should be a number from {} to {}", value, field, min, max))
+    };
+
+    let mut values = Vec::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, step),
+                _ => return Err(format!("`{}` in `{}` should be a positive step", step, field))
+            },
+            None => (part, 1)
+        };
+
+        let (first, last) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((first, last)) => (number(first)?, number(last)?),
+            // "5/10" starts at 5 and keeps going
+            None if step > 1 => (number(range)?, max),
+            None => (number(range)?, number(range)?)
+        };
+
+        if first > last {
+            return Err(format!("`{}` in `{}` is a range that runs backwards", range, field));
+        }
+
+        values.extend((first..=last).step_by(step as usize));
+    }
+
+    values.sort();
+    values.dedup();
+    Ok(Some(values))
+}
+
+// "every day at 7 am", "weekdays at 6:45" or "every saturday and sunday at half past nine"
+fn parse_spoken(expression: &str) -> Result<Recurrence, String> {
+    let words = words(expression);
+    let Some(at) = words.iter().position(|word| word == "at") else {
+        return Err(format!("`{}` needs a time of day, e.g. `every weekday at 6:45`", expression));
+    };
+
+    let mut weekdays = Vec::new();
+    for word in &words[..at] {
+        if DAY_FILLERS.contains(&word.as_str()) {
+            continue;
+        }
+
+        match word.as_str() {
+            "day" | "days" | "daily" => weekdays.extend(0..7),
+            "weekday" | "weekdays" => weekdays.extend(1..6),
+            "weekend" | "weekends" => weekdays.extend([0, 6]),
+            day => match WEEKDAYS.iter().find(|weekday| is_weekday(day, **weekday)) {
+                Some(weekday) => weekdays.push(weekday.num_days_from_sunday()),
+                None => return Err(format!("`{}` in `{}` isn't a day", day, expression))
+            }
+        }
+    }
+    weekdays.sort();
+    weekdays.dedup();
+
+    let (hour, minute, is_pm) = clock_time(&words[at + 1..])
+        .ok_or_else(|| format!("`{}` doesn't say a time of day Jarvis understands", expression))?;
+    let hour = match is_pm {
+        Some(is_pm) => on_24_hour_clock(hour, is_pm),
+        None => hour
+    };
+
+    Ok(Recurrence {
+        minutes: Some(vec![minute]),
+        hours: Some(vec![hour]),
+        days_of_month: None,
+        months: None,
+        weekdays: (!weekdays.is_empty() && weekdays.len() < 7).then_some(weekdays)
+    })
+}
+
+// "monday", "mondays" and "mon" all count
+fn is_weekday(word: &str, weekday: Weekday) -> bool {
+    let name = weekday_name(weekday);
+    word == name || word.strip_suffix('s') == Some(name) || word == &name[..3]
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Local, TimeZone};
+
+    use super::*;
+
+    // 6 May 2024 is a Monday
+    fn on(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 5, day, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn a_spoken_and_a_crontab_schedule_can_mean_the_same() {
+        assert_eq!(parse_recurrence("every weekday at 6:45"), parse_recurrence("45 6 * * 1-5"));
+        assert_eq!(parse_recurrence("weekends at half past nine"), parse_recurrence("30 9 * * 0,6"));
+        assert_eq!(parse_recurrence("every day at 7 pm"), parse_recurrence("0 19 * * *"));
+    }
+
+    #[test]
+    fn weekday_routines_skip_the_weekend() {
+        let recurrence = parse_recurrence("every weekday at 6:45").unwrap();
+
+        assert!(recurrence.matches(on(6, 6, 45)));
+        assert!(recurrence.matches(on(10, 6, 45)));
+        assert!(!recurrence.matches(on(11, 6, 45)));
+        assert!(!recurrence.matches(on(6, 6, 46)));
+    }
+
+    #[test]
+    fn crontab_steps_and_sunday_as_seven() {
+        let every_quarter = parse_recurrence("*/15 * * * *").unwrap();
+        assert!(every_quarter.matches(on(8, 13, 30)));
+        assert!(!every_quarter.matches(on(8, 13, 31)));
+
+        let sundays = parse_recurrence("0 9 * * 7").unwrap();
+        assert!(sundays.matches(on(12, 9, 0)));
+        assert!(!sundays.matches(on(13, 9, 0)));
+    }
+
+    #[test]
+    fn mistakes_say_what_is_wrong() {
+        assert_eq!(parse_recurrence("60 6 * * *"), Err("`60` in `60` should be a number from 0 to 59".to_string()));
+        assert_eq!(parse_recurrence("0 6 * * 5-1"), Err("`5-1` in `5-1` is a range that runs backwards".to_string()));
+        assert_eq!(parse_recurrence("every someday at 7"), Err("`someday` in `every someday at 7` isn't a day".to_string()));
+        assert_eq!(
+            parse_recurrence("every weekday"),
+            Err("`every weekday` needs a time of day, e.g. `every weekday at 6:45`".to_string())
+        );
+    }
+}
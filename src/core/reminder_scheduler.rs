@@ -0,0 +1,187 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local, TimeDelta};
+
+use crate::model::reminder::{Reminder, ReminderOutcome, ReminderRequest};
+
+use super::persisted::{load_json, save_json};
+
+// A reminder nobody reacted to goes off again after a while, and
+// that's also how long it can still be snoozed or dismissed
+const REPEAT_INTERVAL: TimeDelta = TimeDelta::minutes(5);
+const MAX_RINGS: u32 = 3;
+
+/// Keeps track of reminders until they're due. Unlike scheduled commands a
+/// reminder that was due while Jarvis was off still goes off, late is better
+/// than never for those.
+pub struct ReminderScheduler {
+    file: PathBuf,
+    pending: Vec<Reminder>,
+    // The reminders that went off lately and when, snoozing and dismissing act on all of them
+    ringing: Vec<(Reminder, DateTime<Local>)>
+}
+
+impl ReminderScheduler {
+    pub fn load(file: PathBuf) -> Self {
+        let pending: Vec<Reminder> = load_json(&file, "reminders");
+
+        if !pending.is_empty() {
+            println!("Loaded {} reminders from {}", pending.len(), file.display());
+        }
+
+        ReminderScheduler { file, pending, ringing: Vec::new() }
+    }
+
+    pub fn handle(&mut self, request: ReminderRequest) -> ReminderOutcome {
+        let now = Local::now();
+
+        let outcome = match request {
+            ReminderRequest::Add { text, due } => {
+                let reminder = Reminder { id: self.next_id(), text, due, rings: 0 };
+                self.add(reminder.clone());
+                ReminderOutcome::Added(reminder)
+            }
+            ReminderRequest::Snooze(duration) => {
+                let due = now + TimeDelta::from_std(duration).unwrap_or(REPEAT_INTERVAL);
+                let snoozed: Vec<Reminder> = self.take_ringing(now)
+                    .into_iter()
+                    .map(|reminder| Reminder { due, rings: 0, ..reminder })
+                    .collect();
+
+                for reminder in &snoozed {
+                    self.add(reminder.clone());
+                }
+
+                if snoozed.is_empty() {
+                    ReminderOutcome::NothingRinging
+                } else {
+                    ReminderOutcome::Snoozed(snoozed)
+                }
+            }
+            ReminderRequest::Dismiss => {
+                let dismissed = self.take_ringing(now);
+
+                if dismissed.is_empty() {
+                    ReminderOutcome::NothingRinging
+                } else {
+                    ReminderOutcome::Dismissed(dismissed)
+                }
+            }
+        };
+        self.save();
+
+        println!("Reminder {:?}", outcome);
+        outcome
+    }
+
+    /// The reminders that should go off now. Each one is lined up to go
+    /// off again in case nobody heard it.
+    pub fn take_due(&mut self, now: DateTime<Local>) -> Vec<Reminder> {
+        if self.pending.iter().all(|reminder| reminder.due > now) {
+            return Vec::new();
+        }
+
+        let (due, pending): (Vec<Reminder>, Vec<Reminder>) = self.pending
+            .drain(..)
+            .partition(|reminder| reminder.due <= now);
+        self.pending = pending;
+
+        for reminder in &due {
+            if reminder.rings + 1 < MAX_RINGS {
+                self.add(Reminder { due: now + REPEAT_INTERVAL, rings: reminder.rings + 1, ..reminder.clone() });
+            }
+        }
+
+        // Reminders that went off a little earlier can still be snoozed along with these
+        self.ringing.retain(|(ringing, rang_at)| now - *rang_at <= REPEAT_INTERVAL && !due.iter().any(|reminder| reminder.id == ringing.id));
+        self.ringing.extend(due.iter().map(|reminder| (reminder.clone(), now)));
+        self.save();
+
+        due
+    }
+
+    // Removes the reminders that just went off so they don't go off again
+    fn take_ringing(&mut self, now: DateTime<Local>) -> Vec<Reminder> {
+        let ringing: Vec<Reminder> = self.ringing
+            .drain(..)
+            .filter(|(_, rang_at)| now - *rang_at <= REPEAT_INTERVAL)
+            .map(|(reminder, _)| reminder)
+            .collect();
+
+        self.pending.retain(|pending| !ringing.iter().any(|reminder| reminder.id == pending.id));
+        ringing
+    }
+
+    fn add(&mut self, reminder: Reminder) {
+        self.pending.push(reminder);
+        self.pending.sort_by_key(|reminder| reminder.due);
+    }
+
+    // Ringing reminders may not be pending anymore but they still own their ids
+    fn next_id(&self) -> u64 {
+        self.pending
+            .iter()
+            .chain(self.ringing.iter().map(|(reminder, _)| reminder))
+            .map(|reminder| reminder.id)
+            .max()
+            .unwrap_or(0) + 1
+    }
+
+    fn save(&self) {
+        save_json(&self.file, "reminders", &self.pending);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler(name: &str) -> ReminderScheduler {
+        let file = std::env::temp_dir().join(format!("jarvis-test-{}-{}.json", name, std::process::id()));
+        let _ = std::fs::remove_file(&file);
+
+        ReminderScheduler::load(file)
+    }
+
+    fn add(scheduler: &mut ReminderScheduler, text: &str, due: DateTime<Local>) {
+        scheduler.handle(ReminderRequest::Add { text: text.to_string(), due });
+    }
+
+    #[test]
+    fn dismiss_stops_every_reminder_that_rang_together() {
+        let mut scheduler = scheduler("dismiss");
+        let now = Local::now();
+        add(&mut scheduler, "to water the plants", now);
+        add(&mut scheduler, "to call mum", now);
+
+        assert_eq!(scheduler.take_due(now).len(), 2);
+        match scheduler.handle(ReminderRequest::Dismiss) {
+            ReminderOutcome::Dismissed(dismissed) => assert_eq!(dismissed.len(), 2),
+            outcome => panic!("Unexpected {:?}", outcome)
+        };
+        assert!(scheduler.take_due(now + REPEAT_INTERVAL * 2).is_empty());
+    }
+
+    #[test]
+    fn snooze_moves_every_ringing_reminder() {
+        let mut scheduler = scheduler("snooze");
+        let now = Local::now();
+        add(&mut scheduler, "to water the plants", now);
+        add(&mut scheduler, "to call mum", now);
+        scheduler.take_due(now);
+
+        match scheduler.handle(ReminderRequest::Snooze(std::time::Duration::from_secs(30 * 60))) {
+            ReminderOutcome::Snoozed(snoozed) => assert_eq!(snoozed.len(), 2),
+            outcome => panic!("Unexpected {:?}", outcome)
+        };
+        assert!(scheduler.take_due(now + REPEAT_INTERVAL * 2).is_empty());
+        assert_eq!(scheduler.take_due(now + TimeDelta::minutes(31)).len(), 2);
+    }
+
+    #[test]
+    fn nothing_rings_without_a_due_reminder() {
+        let mut scheduler = scheduler("nothing");
+
+        assert!(matches!(scheduler.handle(ReminderRequest::Dismiss), ReminderOutcome::NothingRinging));
+    }
+}
@@ -0,0 +1,82 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
+use chrono::{DateTime, DurationRound, Local, TimeDelta};
+
+use crate::model::routine::{Routine, RoutineOutcome, RoutineRequest};
+
+use super::persisted::{load_json, save_json};
+
+// Minutes skipped while the executor was busy still count, as long as it
+// wasn't for so long that running the routine now would be surprising
+const MISSED_GRACE_PERIOD: TimeDelta = TimeDelta::minutes(5);
+
+/// Decides when the routines from the command map are due. Which routines
+/// were paused by voice is written to disk so they stay paused after a restart.
+pub struct RoutineRunner {
+    file: PathBuf,
+    paused: BTreeSet<String>,
+    last_checked: Option<DateTime<Local>>
+}
+
+impl RoutineRunner {
+    pub fn load(file: PathBuf) -> Self {
+        let paused: BTreeSet<String> = load_json(&file, "paused routines");
+
+        if !paused.is_empty() {
+            println!("Routines paused before the restart: {:?}", paused);
+        }
+
+        RoutineRunner { file, paused, last_checked: None }
+    }
+
+    pub fn handle(&mut self, request: RoutineRequest, routines: &[Routine]) -> RoutineOutcome {
+        let name = match request {
+            RoutineRequest::Pause(ref name) | RoutineRequest::Resume(ref name) => name.clone()
+        };
+
+        if !routines.iter().any(|routine| routine.name == name) {
+            return RoutineOutcome::NotFound(name);
+        }
+
+        let outcome = match request {
+            RoutineRequest::Pause(_) => {
+                self.paused.insert(name.clone());
+                RoutineOutcome::Paused(name)
+            }
+            RoutineRequest::Resume(_) => {
+                self.paused.remove(&name);
+                RoutineOutcome::Resumed(name)
+            }
+        };
+        self.save();
+
+        println!("Routine {:?}", outcome);
+        outcome
+    }
+
+    /// The routines that should run now. Each minute is only looked at
+    /// once, however often this is called.
+    pub fn take_due<'a>(&mut self, now: DateTime<Local>, routines: &'a [Routine]) -> Vec<&'a Routine> {
+        let minute = now.duration_trunc(TimeDelta::minutes(1)).unwrap_or(now);
+        let first = match self.last_checked {
+            Some(last) if last >= minute => return Vec::new(),
+            Some(last) => (last + TimeDelta::minutes(1)).max(minute - MISSED_GRACE_PERIOD),
+            None => minute
+        };
+        self.last_checked = Some(minute);
+
+        let minutes: Vec<DateTime<Local>> = std::iter::successors(Some(first), |time| Some(*time + TimeDelta::minutes(1)))
+            .take_while(|time| *time <= minute)
+            .collect();
+
+        routines
+            .iter()
+            .filter(|routine| !self.paused.contains(&routine.name))
+            .filter(|routine| minutes.iter().any(|time| routine.recurrence.matches(*time)))
+            .collect()
+    }
+
+    fn save(&self) {
+        save_json(&self.file, "paused routines", &self.paused);
+    }
+}
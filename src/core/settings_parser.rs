@@ -0,0 +1,15 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::model::settings::Settings;
+
+/// Reads settings.yaml. Without one every setting has its default.
+pub fn parse_settings(file: Option<&Path>) -> Result<Settings> {
+    let Some(file) = file else {
+        return Ok(Settings::default());
+    };
+
+    let contents = std::fs::read_to_string(file).with_context(|| format!("Could not read {}", file.display()))?;
+    serde_yaml::from_str(&contents).with_context(|| format!("Invalid settings in {}", file.display()))
+}
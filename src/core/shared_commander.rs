@@ -0,0 +1,160 @@
+use std::{collections::HashMap, sync::{Arc, Mutex, RwLock}, time::Duration};
+
+use anyhow::{anyhow, Result};
+
+use crate::{backends::home_assistant_backend::HomeAssistantClient, model::{backend_config::{BackendConfig, HomeAssistantConfig}, command::Command, command_action::{ActionKind, CommandAction, CommandGradientValue, CommandSwitchValue}, command_map::CommandMap, device::{Device, DeviceAddress}}};
+
+use super::{command_map_parser::parse_command_map, commander::Commander, config_paths::ConfigPaths, file_watcher::FileWatcher, jarvis_signals::JarvisSignals};
+
+// How often we check whether the command map changed
+const COMMAND_MAP_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// The areas and entity ids each Home Assistant backend reported, by backend name
+type HomeAssistantImports = HashMap<String, Vec<(String, String)>>;
+
+/// The command map every thread works with. It's loaded, Home Assistant devices
+/// included, in one place and swapped out as a whole when the file changes so
+/// all threads always see the same devices.
+pub struct SharedCommander {
+    current: RwLock<Arc<Commander>>,
+    // Reused when Home Assistant can't be reached on a reload
+    imports: Mutex<HomeAssistantImports>
+}
+
+impl SharedCommander {
+    /// Fails if Home Assistant can't be reached, Jarvis would start without its devices otherwise
+    pub fn load(paths: &ConfigPaths) -> Result<Self> {
+        let mut imports = HomeAssistantImports::new();
+        let commander = load_commander(paths, &mut imports)?;
+
+        Ok(SharedCommander {
+            current: RwLock::new(Arc::new(commander)),
+            imports: Mutex::new(imports)
+        })
+    }
+
+    /// The latest command map. Holding on to it doesn't block reloads, a thread
+    /// can tell it was reloaded by comparing it with `Arc::ptr_eq`.
+    pub fn current(&self) -> Arc<Commander> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Reloads the command map whenever the file changes, until Jarvis shuts down.
+    /// An invalid map is logged and the current one kept.
+    pub fn watch(&self, paths: &ConfigPaths, signals: &JarvisSignals) -> Result<()> {
+        let mut watcher = FileWatcher::new(paths.command_map()?);
+
+        while !signals.is_shutdown() {
+            std::thread::sleep(COMMAND_MAP_POLL_INTERVAL);
+            if !watcher.has_changed() {
+                continue;
+            }
+
+            let reloaded = load_commander(paths, &mut self.imports.lock().unwrap());
+            match reloaded {
+                Ok(reloaded) => {
+                    println!("Command map reloaded with {} commands in {} locations", reloaded.commands.len(), reloaded.locations.len());
+                    *self.current.write().unwrap() = Arc::new(reloaded);
+                }
+                Err(e) => eprintln!("Keeping the previous command map, the new one is invalid: {}", e)
+            };
+        }
+
+        Ok(())
+    }
+}
+
+fn load_commander(paths: &ConfigPaths, imports: &mut HomeAssistantImports) -> Result<Commander> {
+    let file = paths.command_map()?;
+    let (mut map, mut locations) = parse_command_map(&file.to_string_lossy())?;
+
+    let importing: Vec<(String, HomeAssistantConfig)> = map.backends
+        .iter()
+        .filter_map(|(name, backend)| match backend {
+            BackendConfig::HomeAssistant(config) if config.import => Some((name.clone(), config.clone())),
+            _ => None
+        })
+        .collect();
+
+    // A reload keeps the devices Home Assistant reported last time if it's unreachable
+    for (name, config) in importing {
+        let entities = match HomeAssistantClient::new(&config).area_entities() {
+            Ok(entities) => {
+                imports.insert(name.clone(), entities.clone());
+                entities
+            }
+            Err(e) => match imports.get(&name) {
+                Some(entities) => {
+                    eprintln!("Could not reach Home Assistant backend '{}', keeping the devices it had: {}", name, e);
+                    entities.clone()
+                }
+                None => return Err(anyhow!("Could not import devices from Home Assistant backend '{}': {}", name, e))
+            }
+        };
+
+        import_home_assistant(&name, &config, entities, &mut map, &mut locations)?;
+    }
+
+    let commander = Commander::new(map, locations);
+    for automation in &commander.automations {
+        let trigger = &automation.trigger;
+        if commander.devices_of(&trigger.location, &trigger.subject).is_empty() {
+            eprintln!("Automation '{}' waits for the {} in {} but there's no such device, it won't run", automation.name, trigger.subject.name, trigger.location);
+        }
+    }
+
+    Ok(commander)
+}
+
+// Adds every entity Home Assistant has in an area as a device of the location with
+// the same name. Devices declared in the command map take precedence.
+fn import_home_assistant(
+    backend: &str,
+    config: &HomeAssistantConfig,
+    entities: Vec<(String, String)>,
+    map: &mut CommandMap,
+    locations: &mut Vec<String>
+) -> Result<()> {
+    for (area, entity_id) in entities {
+        let domain = entity_id.split('.').next().unwrap_or_default();
+        let Some(label) = config.domains.get(domain) else {
+            continue;
+        };
+        let Some(definition) = map.subjects.iter().find(|definition| definition.label == *label) else {
+            return Err(anyhow!("domain `{}` is mapped to unknown subject `{}`", domain, label));
+        };
+
+        if map.devices.iter().any(|device| device.address == DeviceAddress::EntityId(entity_id.clone())) {
+            continue;
+        }
+
+        let location = area.to_lowercase();
+        if !locations.contains(&location) {
+            locations.push(location.clone());
+        }
+
+        let subject = definition.subject();
+        for kind in &definition.actions {
+            let action = match kind {
+                ActionKind::Switch => CommandAction::Switch(CommandSwitchValue::Off),
+                ActionKind::Gradient => CommandAction::Gradient(CommandGradientValue::Min)
+            };
+
+            let command = Command { location: location.clone(), action, subject: subject.clone() };
+            if !map.commands.iter().any(|existing| existing.location == command.location && existing.subject == command.subject && existing.action.is_same_action(&command.action)) {
+                map.commands.push(command);
+            }
+        }
+
+        map.devices.push(Device {
+            id: entity_id.clone(),
+            location,
+            subject,
+            backend: backend.to_string(),
+            address: DeviceAddress::EntityId(entity_id),
+            state_topic: None
+        });
+    }
+
+    Ok(())
+}
@@ -1,11 +1,10 @@
-use std::path::PathBuf;
-
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperError};
 
 use super::constants::AUDIO_SAMPLE_RATE;
 
 pub struct SpeechRecognizer {
-    context: WhisperContext
+    context: WhisperContext,
+    prompt: Option<String>
 }
 
 // I don't like whisper's logging because I don't need it.
@@ -28,17 +27,15 @@ impl SpeechRecognizer {
             .expect("Unable to create SpeechRecognizer WhisperContext. Did you specify the correct path?");
 
         SpeechRecognizer {
-            context
+            context,
+            prompt: None
         }
     }
- 
-    pub fn default_model_path() -> String {
-        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-            .join("models")
-            .join("ggml-model-whisper-tiny.en.bin")
-            .to_str()
-            .expect("No speech recognizer model found at the default path.")
-            .to_owned()
+
+    /// Whisper is a lot more likely to recognize our room and device
+    /// names if it has seen them in the prompt beforehand
+    pub fn set_vocabulary(&mut self, vocabulary: &[String]) {
+        self.prompt = Some(vocabulary.join(", "));
     }
 
     /// Make sure audio is in 1 channel 16k sampling
@@ -49,6 +46,9 @@ impl SpeechRecognizer {
         let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
         params.set_print_progress(false);
         params.set_single_segment(true);
+        if let Some(ref prompt) = self.prompt {
+            params.set_initial_prompt(prompt);
+        }
 
         // If less than 1 second we need to pad
         let dif = (AUDIO_SAMPLE_RATE as i64) - (audio.len() as i64);
@@ -0,0 +1,363 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Days, Local, NaiveTime};
+
+const UNITS: [(&str, u64); 8] = [
+    ("second", 1), ("seconds", 1), ("sec", 1), ("secs", 1),
+    ("minute", 60), ("minutes", 60), ("hour", 3600), ("hours", 3600)
+];
+
+const FRACTIONS: [(&str, f64); 2] = [("half", 0.5), ("quarter", 0.25)];
+
+// Words that can sit between the parts of a duration, e.g. "a quarter of an hour"
+const FILLERS: [&str; 2] = ["and", "of"];
+
+// Said after the hour, e.g. "seven in the evening"
+const MERIDIEMS: [(&str, bool); 8] = [
+    ("am", false), ("a.m", false), ("morning", false),
+    ("pm", true), ("p.m", true), ("afternoon", true), ("evening", true), ("tonight", true)
+];
+
+// Besides numbers, units and meridiems, what can be said in a delay or a time of day,
+// e.g. "in half an hour" or "at a quarter past seven in the evening"
+const TIME_PHRASE_WORDS: [&str; 8] = ["a", "an", "past", "to", "noon", "midnight", "in", "the"];
+
+const ONES: [&str; 20] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+    "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen", "nineteen"
+];
+
+const TENS: [(&str, u32); 8] = [
+    ("twenty", 20), ("thirty", 30), ("forty", 40), ("fifty", 50),
+    ("sixty", 60), ("seventy", 70), ("eighty", 80), ("ninety", 90)
+];
+
+/// Splits a transcript into lowercase words without punctuation
+pub fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .replace('-', " ")
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric() && c != '.' && c != ':').trim_end_matches('.').to_string())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// A number written out as digits or a single word, e.g. "7" or "seven"
+pub fn spoken_number(word: &str) -> Option<u32> {
+    if let Ok(number) = word.parse::<u32>() {
+        return Some(number);
+    }
+
+    ONES.iter()
+        .position(|one| *one == word)
+        .map(|number| number as u32)
+        .or_else(|| TENS.iter().find(|(tens, _)| *tens == word).map(|(_, number)| *number))
+}
+
+/// Finds a duration in a transcript, e.g. "ten minutes", "an hour and a half"
+/// or "2 hours 15 minutes". Whisper writes numbers either way so both work.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let mut seconds = 0.0;
+    let mut pending: Option<f64> = None;
+    // "a" and "an" only count as one if a unit follows
+    let mut is_article = false;
+    let mut last_unit: Option<u64> = None;
+
+    for word in words(text) {
+        if let Some((_, unit)) = UNITS.iter().find(|(name, _)| *name == word) {
+            seconds += pending.unwrap_or(1.0) * *unit as f64;
+            last_unit = Some(*unit);
+            pending = None;
+            is_article = false;
+        } else if let Some((_, fraction)) = FRACTIONS.iter().find(|(name, _)| *name == word) {
+            pending = match pending {
+                Some(number) if !is_article => Some(number + fraction),
+                _ => Some(*fraction)
+            };
+            is_article = false;
+        } else if word == "a" || word == "an" {
+            if pending.is_none() {
+                pending = Some(1.0);
+                is_article = true;
+            }
+        } else if let Some(number) = word.parse::<f64>().ok().or_else(|| spoken_number(&word).map(f64::from)) {
+            // "twenty five" is said as two words
+            pending = match pending {
+                Some(tens) if !is_article && number < 10.0 && tens >= 20.0 && tens % 10.0 == 0.0 => Some(tens + number),
+                _ => Some(number)
+            };
+            is_article = false;
+        } else if !FILLERS.contains(&word.as_str()) {
+            pending = None;
+            is_article = false;
+        }
+    }
+
+    // "an hour and a half" puts the fraction after the unit
+    if let (Some(fraction), Some(unit)) = (pending, last_unit) {
+        if fraction < 1.0 {
+            seconds += fraction * unit as f64;
+        }
+    }
+
+    (seconds > 0.0).then(|| Duration::from_secs(seconds.round() as u64))
+}
+
+/// Finds when something should happen if the words end with it, either after a
+/// delay ("in 20 minutes") or at a time of day ("at 7 am", "at half past six").
+/// Times without am or pm are taken to mean whichever comes first. Returns
+/// where the time starts so it can be left out of the rest.
+pub fn trailing_due_time(words: &[String], now: DateTime<Local>) -> Option<(usize, DateTime<Local>)> {
+    // "at" isn't part of the phrase so it's where the search starts if it's there
+    let start = words.iter().rposition(|word| !is_time_phrase_word(word)).unwrap_or_default();
+
+    (start..words.len()).find_map(|index| due_time_at(&words[index..], now).map(|due| (index, due)))
+}
+
+fn is_time_phrase_word(word: &str) -> bool {
+    TIME_PHRASE_WORDS.contains(&word)
+        || UNITS.iter().any(|(name, _)| *name == word)
+        || FRACTIONS.iter().any(|(name, _)| *name == word)
+        || FILLERS.contains(&word)
+        || MERIDIEMS.iter().any(|(name, _)| *name == word)
+        || word.parse::<f64>().is_ok()
+        || spoken_number(word).is_some()
+        // "7:30" or "7pm"
+        || clock_time(&[word.to_string()]).is_some()
+}
+
+/// When something should happen if the words start with a delay or a time
+/// of day, so the caller knows where it was said
+pub fn due_time_at(words: &[String], now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let (word, rest) = words.split_first()?;
+
+    match word.as_str() {
+        "in" if starts_number(rest) => parse_duration(&rest.join(" "))
+            .and_then(|delay| chrono::Duration::from_std(delay).ok())
+            .map(|delay| now + delay),
+        "at" => clock_time(rest).and_then(|(hour, minute, is_pm)| next_occurrence(hour, minute, is_pm, now)),
+        _ => None
+    }
+}
+
+fn starts_number(words: &[String]) -> bool {
+    words.first().is_some_and(|word| {
+        ["a", "an", "half"].contains(&word.as_str()) || word.parse::<f64>().is_ok() || spoken_number(word).is_some()
+    })
+}
+
+/// The hour and minute as said, e.g. "half past six" or "7:30 pm", with
+/// whether it's am or pm if that was said too
+pub fn clock_time(words: &[String]) -> Option<(u32, u32, Option<bool>)> {
+    let word = |index: usize| words.get(index).map(String::as_str).unwrap_or_default();
+    let is_quarter_to = (word(0), word(1)) == ("quarter", "to");
+
+    let (hour, minute, next) = match (word(0), word(1)) {
+        ("noon", _) => (12, 0, 1),
+        ("midnight", _) => (0, 0, 1),
+        ("half", "past") => (spoken_number(word(2))?, 30, 3),
+        ("quarter", "past") => (spoken_number(word(2))?, 15, 3),
+        ("quarter", "to") => (spoken_number(word(2))?, 45, 3),
+        (first, _) => {
+            // Whisper writes "7:30", "7pm" or "seven thirty"
+            let (digits, suffix) = match first.find(|c: char| !c.is_ascii_digit() && c != ':') {
+                Some(index) if index > 0 => first.split_at(index),
+                _ => (first, "")
+            };
+            let (hour, minute) = match digits.split_once(':') {
+                Some((hour, minute)) => (hour.parse().ok()?, minute.parse().ok()?),
+                None => (spoken_number(digits)?, 0)
+            };
+
+            if !suffix.is_empty() {
+                let is_pm = MERIDIEMS.iter().find(|(name, _)| *name == suffix).map(|(_, is_pm)| *is_pm)?;
+                return valid_time(hour, minute, Some(is_pm));
+            }
+
+            match (spoken_number(word(1)), spoken_number(word(2))) {
+                (Some(tens), Some(ones)) if minute == 0 && tens >= 20 && ones < 10 => (hour, tens + ones, 3),
+                (Some(minutes), _) if minute == 0 && word(1).parse::<u32>().is_err() => (hour, minutes, 2),
+                _ => (hour, minute, 1)
+            }
+        }
+    };
+
+    let is_pm = words[next.min(words.len())..]
+        .iter()
+        .take(3)
+        .find_map(|word| MERIDIEMS.iter().find(|(name, _)| name == word).map(|(_, is_pm)| *is_pm));
+
+    if is_quarter_to {
+        return quarter_to(hour, is_pm);
+    }
+
+    valid_time(hour, minute, is_pm)
+}
+
+/// The hour of the day for an hour said with am or pm
+pub fn on_24_hour_clock(hour: u32, is_pm: bool) -> u32 {
+    match (hour, is_pm) {
+        (0..=11, true) => hour + 12,
+        (12, false) => 0,
+        _ => hour
+    }
+}
+
+// The am or pm is about the hour that's named, so "quarter to twelve am" is just
+// before midnight. Without one "quarter to one" stays on the 12 hour clock as 12:45.
+fn quarter_to(hour: u32, is_pm: Option<bool>) -> Option<(u32, u32, Option<bool>)> {
+    if hour > 24 {
+        return None;
+    }
+
+    match is_pm {
+        Some(is_pm) => {
+            let hour = (on_24_hour_clock(hour, is_pm) + 23) % 24;
+            valid_time(hour, 45, Some(hour >= 12))
+        }
+        // Already on a 24 hour clock, e.g. "quarter to 15"
+        None if hour > 12 => valid_time(hour - 1, 45, Some(true)),
+        None if hour == 1 => valid_time(12, 45, None),
+        None => valid_time((hour + 23) % 24, 45, None)
+    }
+}
+
+fn valid_time(hour: u32, minute: u32, is_pm: Option<bool>) -> Option<(u32, u32, Option<bool>)> {
+    (hour < 24 && minute < 60).then_some((hour, minute, is_pm))
+}
+
+fn next_occurrence(hour: u32, minute: u32, is_pm: Option<bool>, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let hours = match is_pm {
+        Some(is_pm) => vec![on_24_hour_clock(hour, is_pm)],
+        None if hour == 0 || hour > 12 => vec![hour],
+        None => vec![hour % 12, hour % 12 + 12]
+    };
+
+    hours
+        .into_iter()
+        .filter_map(|hour| {
+            let time = NaiveTime::from_hms_opt(hour, minute, 0)?;
+            let today = now.date_naive().and_time(time).and_local_timezone(Local).earliest()?;
+
+            if today > now {
+                Some(today)
+            } else {
+                today.checked_add_days(Days::new(1))
+            }
+        })
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 5, 6, hour, minute, 0).unwrap()
+    }
+
+    fn tomorrow_at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 5, 7, hour, minute, 0).unwrap()
+    }
+
+    fn seconds(text: &str) -> Option<u64> {
+        parse_duration(text).map(|duration| duration.as_secs())
+    }
+
+    #[test]
+    fn durations_add_up_their_parts() {
+        assert_eq!(seconds("set a timer for 2 hours 15 minutes"), Some(8100));
+        assert_eq!(seconds("an hour and a half"), Some(5400));
+        assert_eq!(seconds("a quarter of an hour"), Some(900));
+        assert_eq!(seconds("1.5 hours"), Some(5400));
+    }
+
+    #[test]
+    fn durations_can_be_spelled_out() {
+        assert_eq!(seconds("twenty five seconds"), Some(25));
+        assert_eq!(seconds("ten minutes"), seconds("10 minutes"));
+    }
+
+    #[test]
+    fn numbers_and_articles_need_a_unit() {
+        assert_eq!(seconds("set a timer"), None);
+        assert_eq!(seconds("ten"), None);
+    }
+
+    #[test]
+    fn whisper_writes_times_in_digits_or_words() {
+        assert_eq!(clock_time(&words("7:30")), Some((7, 30, None)));
+        assert_eq!(clock_time(&words("7pm")), Some((7, 0, Some(true))));
+        assert_eq!(clock_time(&words("seven twenty five in the evening")), Some((7, 25, Some(true))));
+        assert_eq!(clock_time(&words("half past six")), Some((6, 30, None)));
+        assert_eq!(clock_time(&words("noon")), Some((12, 0, None)));
+    }
+
+    #[test]
+    fn impossible_times_are_not_times() {
+        assert_eq!(clock_time(&words("25:00")), None);
+        assert_eq!(clock_time(&words("7:60")), None);
+        assert_eq!(clock_time(&words("half past")), None);
+    }
+
+    #[test]
+    fn a_time_without_am_or_pm_is_due_at_whichever_comes_first() {
+        let due = |text: &str, now| trailing_due_time(&words(text), now).map(|(_, due)| due);
+
+        assert_eq!(due("open the blinds at 7", at(6, 0)), Some(at(7, 0)));
+        assert_eq!(due("open the blinds at 7", at(10, 0)), Some(at(19, 0)));
+        assert_eq!(due("open the blinds at 7 am", at(10, 0)), Some(tomorrow_at(7, 0)));
+        assert_eq!(due("close the blinds at midnight", at(10, 0)), Some(tomorrow_at(0, 0)));
+    }
+
+    #[test]
+    fn quarter_to_stays_on_the_named_side_of_noon() {
+        let cases = [
+            ("quarter to one", Some((12, 45, None))),
+            ("quarter to one am", Some((0, 45, Some(false)))),
+            ("quarter to one pm", Some((12, 45, Some(true)))),
+            ("quarter to seven", Some((6, 45, None))),
+            ("quarter to twelve am", Some((23, 45, Some(true)))),
+            ("quarter to twelve pm", Some((11, 45, Some(false)))),
+            ("quarter to 15", Some((14, 45, Some(true)))),
+            ("quarter to", None)
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(clock_time(&words(text)), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn quarter_to_one_is_due_at_whichever_comes_first() {
+        let cases = [
+            ("turn off the light at quarter to one", at(10, 0), at(12, 45)),
+            ("turn off the light at quarter to one", at(13, 0), tomorrow_at(0, 45)),
+            ("turn off the light at quarter to one am", at(10, 0), tomorrow_at(0, 45)),
+            ("turn off the light at quarter to twelve am", at(10, 0), at(23, 45))
+        ];
+
+        for (text, now, expected) in cases {
+            assert_eq!(trailing_due_time(&words(text), now).map(|(_, due)| due), Some(expected), "{}", text);
+        }
+    }
+
+    #[test]
+    fn only_a_time_at_the_end_counts() {
+        let cases = [
+            ("turn off the living room light in 20 minutes", Some((6, at(10, 20)))),
+            ("open the blinds at seven in the evening", Some((3, at(19, 0)))),
+            ("turn on the light in half an hour", Some((4, at(10, 30)))),
+            ("turn on the light in 2 rooms", None),
+            ("turn on the light at 7 street", None),
+            ("turn on the light in 5 minutes in the kitchen", None),
+            ("turn on the light", None)
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(trailing_due_time(&words(text), at(10, 0)), expected, "{}", text);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use std::{collections::HashMap, sync::{mpsc::{channel, Receiver, Sender}, Mutex}, time::Instant};
+
+use crate::model::device_state::DeviceState;
+
+struct TrackedState {
+    state: DeviceState,
+    changed_at: Instant
+}
+
+/// The last known state of every device, by device id. Updated by the
+/// executor after successful commands and by backends whenever a device
+/// reports its state on its own.
+pub struct StateStore {
+    states: Mutex<HashMap<String, TrackedState>>,
+    // Told the id of every device whose state changed
+    subscribers: Mutex<Vec<Sender<String>>>
+}
+
+impl StateStore {
+    pub fn new() -> Self {
+        StateStore {
+            states: Mutex::new(HashMap::new()),
+            subscribers: Mutex::new(Vec::new())
+        }
+    }
+
+    pub fn get(&self, device_id: &str) -> Option<DeviceState> {
+        self.states
+            .lock()
+            .ok()
+            .and_then(|states| states.get(device_id).map(|tracked| tracked.state))
+    }
+
+    /// When the device's state last changed, a repeated report doesn't count
+    pub fn changed_at(&self, device_id: &str) -> Option<Instant> {
+        self.states
+            .lock()
+            .ok()
+            .and_then(|states| states.get(device_id).map(|tracked| tracked.changed_at))
+    }
+
+    /// The id of every device whose state changes from now on is sent to the returned receiver
+    pub fn subscribe(&self) -> Receiver<String> {
+        let (change_tx, change_rx) = channel();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(change_tx);
+        }
+
+        change_rx
+    }
+
+    pub fn update(&self, device_id: &str, update: impl FnOnce(DeviceState) -> DeviceState) {
+        let Ok(mut states) = self.states.lock() else {
+            return;
+        };
+
+        let tracked = states
+            .entry(device_id.to_string())
+            .or_insert_with(|| TrackedState { state: DeviceState::default(), changed_at: Instant::now() });
+        let state = update(tracked.state);
+        if state == tracked.state {
+            return;
+        }
+
+        tracked.state = state;
+        tracked.changed_at = Instant::now();
+
+        // Subscribers that went away are dropped
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|subscriber| subscriber.send(device_id.to_string()).is_ok());
+        }
+    }
+
+    // Reports usually only contain what changed
+    pub fn report(&self, device_id: &str, reported: DeviceState) {
+        self.update(device_id, |state| DeviceState {
+            on: reported.on.or(state.on),
+            level: reported.level.or(state.level)
+        });
+    }
+}
@@ -0,0 +1,148 @@
+use std::{sync::{mpsc::Sender, Arc, Mutex}, time::{Duration, Instant}};
+
+use tokio::{runtime::Handle, task::JoinHandle};
+
+use crate::model::{speech_request::SpeechRequest, timer::{TimerInfo, TimerOutcome, TimerRequest}};
+
+struct RunningTimer {
+    id: u64,
+    name: Option<String>,
+    ends_at: Instant,
+    alarm: JoinHandle<()>
+}
+
+impl RunningTimer {
+    fn info(&self) -> TimerInfo {
+        TimerInfo {
+            name: self.name.clone(),
+            remaining: self.ends_at.saturating_duration_since(Instant::now())
+        }
+    }
+}
+
+/// Runs countdowns on the tokio runtime. When one runs out the alarm is
+/// sent straight to the speech synthesizer so it rings even when idle.
+pub struct TimerScheduler {
+    runtime: Handle,
+    timers: Arc<Mutex<Vec<RunningTimer>>>,
+    next_id: Mutex<u64>,
+    speech_tx: Sender<SpeechRequest>
+}
+
+impl TimerScheduler {
+    pub fn new(runtime: Handle, speech_tx: Sender<SpeechRequest>) -> Self {
+        TimerScheduler {
+            runtime,
+            timers: Arc::new(Mutex::new(Vec::new())),
+            next_id: Mutex::new(0),
+            speech_tx
+        }
+    }
+
+    pub fn handle(&self, request: TimerRequest) -> TimerOutcome {
+        match request {
+            TimerRequest::Start { name, duration } => self.start(name, duration),
+            TimerRequest::Cancel { name } => self.cancel(name),
+            TimerRequest::TimeLeft { name } => self.time_left(name)
+        }
+    }
+
+    // Starting a timer with the name of a running one replaces it
+    fn start(&self, name: Option<String>, duration: Duration) -> TimerOutcome {
+        let id = match self.next_id.lock() {
+            Ok(mut next_id) => {
+                *next_id += 1;
+                *next_id
+            }
+            Err(_) => 0
+        };
+
+        let timers = self.timers.clone();
+        let speech_tx = self.speech_tx.clone();
+        let announcement = match name {
+            Some(ref name) => format!("Your {} timer is done.", name),
+            None => "Your timer is done.".to_string()
+        };
+
+        let alarm = self.runtime.spawn(async move {
+            tokio::time::sleep(duration).await;
+
+            if let Ok(mut timers) = timers.lock() {
+                timers.retain(|timer| timer.id != id);
+            }
+
+            println!("Timer {} ran out", id);
+            if speech_tx.send(SpeechRequest::Alarm(announcement)).is_err() {
+                eprintln!("Could not ring the alarm, the speech synthesizer is gone");
+            }
+        });
+
+        let timer = RunningTimer {
+            id,
+            name,
+            ends_at: Instant::now() + duration,
+            alarm
+        };
+        let info = timer.info();
+
+        if let Ok(mut timers) = self.timers.lock() {
+            timers.retain(|running| {
+                let is_replaced = running.name == timer.name;
+                if is_replaced {
+                    running.alarm.abort();
+                }
+                !is_replaced
+            });
+            timers.push(timer);
+        }
+
+        println!("Started timer {:?} for {:?}", info.name, duration);
+        TimerOutcome::Started(info)
+    }
+
+    fn cancel(&self, name: Option<String>) -> TimerOutcome {
+        let Ok(mut timers) = self.timers.lock() else {
+            return TimerOutcome::NotFound(name);
+        };
+
+        let index = match find_timer(&timers, &name) {
+            Ok(index) => index,
+            Err(outcome) => return outcome
+        };
+
+        let timer = timers.remove(index);
+        timer.alarm.abort();
+
+        TimerOutcome::Cancelled(timer.info())
+    }
+
+    fn time_left(&self, name: Option<String>) -> TimerOutcome {
+        let Ok(timers) = self.timers.lock() else {
+            return TimerOutcome::NotFound(name);
+        };
+
+        // Without a name every timer is read out
+        match name {
+            None if !timers.is_empty() => TimerOutcome::TimeLeft(timers.iter().map(RunningTimer::info).collect()),
+            _ => match find_timer(&timers, &name) {
+                Ok(index) => TimerOutcome::TimeLeft(vec![timers[index].info()]),
+                Err(outcome) => outcome
+            }
+        }
+    }
+}
+
+// An unnamed request is fine as long as there's only one timer
+fn find_timer(timers: &[RunningTimer], name: &Option<String>) -> Result<usize, TimerOutcome> {
+    match name {
+        Some(_) => timers
+            .iter()
+            .position(|timer| timer.name == *name)
+            .ok_or_else(|| TimerOutcome::NotFound(name.clone())),
+        None => match timers.len() {
+            0 => Err(TimerOutcome::NotFound(None)),
+            1 => Ok(0),
+            _ => Err(TimerOutcome::Ambiguous(timers.iter().map(RunningTimer::info).collect()))
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt::{self, Formatter};
+
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+
+// serde_yaml only knows positions while deserializing, so problems found afterwards
+// are located by walking down to the value again and failing on purpose once we get there
+const FOUND: &str = "path found";
+
+/// The line and column of the value at `path` in a YAML document, e.g.
+/// `["scenes", "movie night", "1", "subject"]`. Sequence items are addressed by
+/// their index. The position points at the key if the path ends with one.
+pub fn yaml_position(contents: &str, path: &[&str]) -> Option<(usize, usize)> {
+    let deserializer = serde_yaml::Deserializer::from_str(contents);
+
+    match PathSeed(path).deserialize(deserializer) {
+        Err(error) if error.to_string().contains(FOUND) => error.location().map(|location| (location.line(), location.column())),
+        _ => None
+    }
+}
+
+struct PathSeed<'a>(&'a [&'a str]);
+
+impl<'de> DeserializeSeed<'de> for PathSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(PathVisitor(self.0))
+    }
+}
+
+// Fails while visiting the value at the end of the path, that's when serde_yaml knows where it is
+struct PathVisitor<'a>(&'a [&'a str]);
+
+impl PathVisitor<'_> {
+    fn scalar<E: de::Error>(self) -> Result<(), E> {
+        match self.0 {
+            [] => Err(E::custom(FOUND)),
+            // Scalars have nothing further down the path
+            _ => Ok(())
+        }
+    }
+}
+
+impl<'de> Visitor<'de> for PathVisitor<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str("a mapping or sequence")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let Some((key, rest)) = self.0.split_first() else {
+            return Err(de::Error::custom(FOUND));
+        };
+
+        while let Some(found) = map.next_key_seed(KeySeed { key, is_last: rest.is_empty() })? {
+            if found {
+                return map.next_value_seed(PathSeed(rest));
+            }
+            map.next_value::<IgnoredAny>()?;
+        }
+
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let Some((index, rest)) = self.0.split_first() else {
+            return Err(de::Error::custom(FOUND));
+        };
+        let Ok(index) = index.parse::<usize>() else {
+            return Ok(());
+        };
+
+        for _ in 0..index {
+            if seq.next_element::<IgnoredAny>()?.is_none() {
+                return Ok(());
+            }
+        }
+        seq.next_element_seed(PathSeed(rest))?;
+
+        Ok(())
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> Result<(), E> {
+        self.scalar()
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<(), E> {
+        self.scalar()
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<(), E> {
+        self.scalar()
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<(), E> {
+        self.scalar()
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<(), E> {
+        self.scalar()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+        self.scalar()
+    }
+}
+
+// Tells whether a mapping key is the next one on the path, failing right away
+// if it's the last one so the position is that of the key
+struct KeySeed<'a> {
+    key: &'a str,
+    is_last: bool
+}
+
+impl<'de> DeserializeSeed<'de> for KeySeed<'_> {
+    type Value = bool;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<bool, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> Visitor<'de> for KeySeed<'_> {
+    type Value = bool;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str("a mapping key")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<bool, E> {
+        match value == self.key {
+            true if self.is_last => Err(E::custom(FOUND)),
+            found => Ok(found)
+        }
+    }
+}
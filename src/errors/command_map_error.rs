@@ -0,0 +1,59 @@
+use std::{error::Error, fmt::{Debug, Display}};
+
+pub struct CommandMapError {
+    file: String,
+    position: Option<(usize, usize)>,
+    message: String
+}
+
+impl CommandMapError {
+    pub fn new(file: &str, message: String) -> Self {
+        CommandMapError {
+            file: file.to_string(),
+            position: None,
+            message
+        }
+    }
+
+    // For problems found after deserializing, the position is looked up separately
+    pub fn at(file: &str, position: Option<(usize, usize)>, message: String) -> Self {
+        CommandMapError {
+            file: file.to_string(),
+            position,
+            message
+        }
+    }
+
+    pub fn from_yaml(file: &str, error: serde_yaml::Error) -> Self {
+        // The position is reported separately so we strip it from the message
+        let message = error.to_string();
+        let message = match message.find(" at line ") {
+            Some(index) => message[..index].to_string(),
+            None => message
+        };
+
+        CommandMapError {
+            file: file.to_string(),
+            position: error.location().map(|location| (location.line(), location.column())),
+            message
+        }
+    }
+}
+
+impl Display for CommandMapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.position {
+            Some((line, column)) => write!(f, "{}:{}:{}: {}", self.file, line, column, self.message),
+            None => write!(f, "{}: {}", self.file, self.message)
+        }
+    }
+}
+
+impl Debug for CommandMapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Command map error {{ {} }}", self)
+    }
+}
+
+impl Error for CommandMapError {
+}
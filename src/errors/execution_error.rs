@@ -0,0 +1,68 @@
+use std::{error::Error, fmt::{Debug, Display}};
+
+/// Why a device didn't do what it was told. The feedback
+/// generator words its answer based on this.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionErrorReason {
+    Unreachable,
+    TimedOut,
+    Rejected,
+    Unsupported,
+    Failed
+}
+
+#[derive(Clone)]
+pub struct ExecutionError {
+    pub reason: ExecutionErrorReason,
+    message: String
+}
+
+impl ExecutionError {
+    pub fn new(reason: ExecutionErrorReason, message: impl Into<String>) -> Self {
+        ExecutionError {
+            reason,
+            message: message.into()
+        }
+    }
+
+    pub fn unreachable(message: impl Into<String>) -> Self {
+        Self::new(ExecutionErrorReason::Unreachable, message)
+    }
+
+    pub fn timed_out(message: impl Into<String>) -> Self {
+        Self::new(ExecutionErrorReason::TimedOut, message)
+    }
+
+    pub fn rejected(message: impl Into<String>) -> Self {
+        Self::new(ExecutionErrorReason::Rejected, message)
+    }
+
+    pub fn unsupported(message: impl Into<String>) -> Self {
+        Self::new(ExecutionErrorReason::Unsupported, message)
+    }
+
+    pub fn failed(message: impl Into<String>) -> Self {
+        Self::new(ExecutionErrorReason::Failed, message)
+    }
+}
+
+impl From<anyhow::Error> for ExecutionError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::failed(error.to_string())
+    }
+}
+
+impl Display for ExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Debug for ExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Execution error {{ reason: {:?}, message: {} }}", self.reason, self.message)
+    }
+}
+
+impl Error for ExecutionError {
+}
@@ -29,7 +29,7 @@ impl Display for JarvisError {
 
 impl Debug for JarvisError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Jarvis error {{ reason: {:?}, message: {} }}", self.reason, self.to_string())
+        write!(f, "Jarvis error {{ reason: {:?}, message: {} }}", self.reason, self)
     }
 }
 
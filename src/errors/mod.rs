@@ -1 +1,3 @@
-pub mod jarvis_error;
\ No newline at end of file
+pub mod jarvis_error;
+pub mod command_map_error;
+pub mod execution_error;
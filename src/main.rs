@@ -3,17 +3,56 @@ mod processing;
 mod model;
 mod traits;
 mod errors;
+mod backends;
+mod answerers;
+mod scorers;
 
 use std::{sync::{mpsc::channel, Arc}, time::Duration};
-use processing::classifier::ClassifierOutput;
-use core::jarvis_signals::JarvisSignals;
+use processing::{classifier::ClassifierOutput, intent_executor::ExecutorOutput};
+use core::{config_paths::ConfigPaths, constants::LOCAL_AUDIO_SOURCE, jarvis_signals::JarvisSignals, settings_parser::parse_settings, shared_commander::SharedCommander};
+use model::{speech_request::SpeechRequest, utterance::Utterance};
 use tokio::{signal, task::JoinSet};
 
 #[tokio::main]
 async fn main() {
+    let paths = match ConfigPaths::from_args() {
+        Ok(paths) => Arc::new(paths),
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    let settings = match parse_settings(paths.settings().as_deref()) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("{:?}", e);
+            return;
+        }
+    };
+
+    // Loaded once for all threads, importing devices from Home Assistant can take a while
+    let commanders = match SharedCommander::load(&paths) {
+        Ok(commanders) => Arc::new(commanders),
+        Err(e) => {
+            eprintln!("{:?}", e);
+            return;
+        }
+    };
+
     let signals = Arc::new(JarvisSignals::new());
     let mut thread_pool = JoinSet::new();
 
+    let watcher_paths = paths.clone();
+    let watcher_commanders = commanders.clone();
+    let watcher_signals = signals.clone();
+    thread_pool.spawn_blocking(move || {
+        watcher_commanders.watch(&watcher_paths, &watcher_signals)
+            .map_err(|e| watcher_signals.set_shutdown(Some(e)))
+            .ok();
+        println!("Command map watcher shutting down");
+    });
+
     let (mic_tx, mic_rx) = channel::<Vec<f32>>();
     let mic_signals = signals.clone();
     let mic_shutdown_signals = signals.clone();
@@ -32,39 +71,54 @@ async fn main() {
     });
 
     let (detector_tx, detector_rx) = channel::<Vec<f32>>();
-    let detector_signals = signals.clone();
+    let detector_shutdown_signals = signals.clone();
     thread_pool.spawn(async move {
         processing::wake_word_detector::main(chunker_rx, detector_tx)
-            .map_err(|e| detector_signals.set_shutdown(Some(e)))
+            .map_err(|e| detector_shutdown_signals.set_shutdown(Some(e)))
             .ok();
         println!("Wake word detector shutting down");
     });
 
-    let (recognizer_tx, recognizer_rx) = channel::<String>();
+    let (recognizer_tx, recognizer_rx) = channel::<Utterance>();
+    let recognizer_paths = paths.clone();
+    let recognizer_commanders = commanders.clone();
+    let recognizer_signals = signals.clone();
     thread_pool.spawn(async move {
-        processing::recognizer::main(detector_rx, recognizer_tx);
+        processing::recognizer::main(recognizer_paths, recognizer_commanders, LOCAL_AUDIO_SOURCE, detector_rx, recognizer_tx)
+            .map_err(|e| recognizer_signals.set_shutdown(Some(e)))
+            .ok();
         println!("Speech recognizer shutting down");
     });
 
     let (classifier_tx, classifier_rx) = channel::<ClassifierOutput>();
-    let classifier_signals = signals.clone();
+    let classifier_commanders = commanders.clone();
+    let classifier_shutdown_signals = signals.clone();
+    let classifier_kind = settings.classifier;
     thread_pool.spawn_blocking(move || {
-        processing::classifier::main(recognizer_rx, classifier_tx)
-            .map_err(|e| classifier_signals.set_shutdown(Some(e)))
+        processing::classifier::main(classifier_kind, classifier_commanders, recognizer_rx, classifier_tx)
+            .map_err(|e| classifier_shutdown_signals.set_shutdown(Some(e)))
             .ok();
         println!("Classifier shutting down");
     });
 
-    let (executor_tx, executor_rx) = channel::<ClassifierOutput>();
-    thread_pool.spawn(async move {
-        processing::intent_executor::main(classifier_rx, executor_tx);
+    // Timers ring on their own so the executor can talk to the speech synthesizer too
+    let (feedback_tx, feedback_rx) = channel::<SpeechRequest>();
+    let (executor_tx, executor_rx) = channel::<ExecutorOutput>();
+    let executor_paths = paths.clone();
+    let executor_commanders = commanders.clone();
+    let executor_signals = signals.clone();
+    let alarm_tx = feedback_tx.clone();
+    thread_pool.spawn_blocking(move || {
+        processing::intent_executor::main(executor_paths, executor_commanders, classifier_rx, executor_tx, alarm_tx)
+            .map_err(|e| executor_signals.set_shutdown(Some(e)))
+            .ok();
         println!("Command executor shutting down");
     });
 
-    let (feedback_tx, feedback_rx) = channel::<String>();
     let feedback_signals = signals.clone();
+    let question_answering = settings.question_answering.clone();
     thread_pool.spawn_blocking(move || {
-        processing::feedback_generator::main(executor_rx, feedback_tx)
+        processing::feedback_generator::main(question_answering, executor_rx, feedback_tx)
             .map_err(|e| feedback_signals.set_shutdown(Some(e)))
             .ok();
         println!("Feedback generator shutting down");
@@ -105,7 +159,7 @@ async fn main() {
 
     println!("Terminating auxiliary threads...");
 
-    while let Some(_) = thread_pool.join_next().await {}
+    while thread_pool.join_next().await.is_some() {}
 
     println!("\nAux threads terminated. Exiting...");
 }
\ No newline at end of file
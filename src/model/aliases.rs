@@ -0,0 +1,50 @@
+use super::clarification::CommandSlot;
+
+/// Maps alternative names to the canonical label they stand for
+#[derive(Debug, Default, Clone)]
+pub struct AliasMap {
+    entries: Vec<(String, String)>
+}
+
+impl AliasMap {
+    pub fn add(&mut self, canonical: &str, alias: &str) {
+        self.entries.push((alias.to_string(), canonical.to_string()));
+    }
+
+    /// Returns the canonical label for an alias or the label itself
+    /// if it isn't an alias
+    pub fn canonical<'a>(&'a self, label: &'a str) -> &'a str {
+        self.entries
+            .iter()
+            .find(|(alias, _)| alias == label)
+            .map_or(label, |(_, canonical)| canonical.as_str())
+    }
+
+    pub fn aliases(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter().map(|(alias, _)| alias)
+    }
+
+    pub fn aliases_of<'a>(&'a self, canonical: &'a str) -> impl Iterator<Item = &'a String> {
+        self.entries
+            .iter()
+            .filter(move |(_, name)| name == canonical)
+            .map(|(alias, _)| alias)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Aliases {
+    pub locations: AliasMap,
+    pub actions: AliasMap,
+    pub subjects: AliasMap
+}
+
+impl Aliases {
+    pub fn for_slot(&self, slot: CommandSlot) -> &AliasMap {
+        match slot {
+            CommandSlot::Location => &self.locations,
+            CommandSlot::Action => &self.actions,
+            CommandSlot::Subject => &self.subjects
+        }
+    }
+}
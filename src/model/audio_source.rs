@@ -0,0 +1,6 @@
+/// A microphone or satellite that feeds audio into the pipeline
+#[derive(Debug, Clone)]
+pub struct AudioSource {
+    pub name: String,
+    pub location: String
+}
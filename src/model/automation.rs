@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use super::{command::Command, command_subject::CommandSubject, device_state::DeviceState};
+
+/// What a device's state is compared against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateCondition {
+    Is(bool),
+    Below(i32),
+    Above(i32)
+}
+
+impl StateCondition {
+    // Nothing holds for a state we don't know
+    pub fn holds(&self, state: &DeviceState) -> bool {
+        match self {
+            StateCondition::Is(on) => state.on == Some(*on),
+            StateCondition::Below(level) => state.level.is_some_and(|current| current < *level),
+            StateCondition::Above(level) => state.level.is_some_and(|current| current > *level)
+        }
+    }
+}
+
+/// Fires when any device of the subject in the location meets the
+/// condition, or once it met it for the whole duration
+#[derive(Debug, Clone)]
+pub struct Trigger {
+    pub location: String,
+    pub subject: CommandSubject,
+    pub condition: StateCondition,
+    pub duration: Option<Duration>
+}
+
+/// Runs commands when a device gets into a certain state, e.g. turning on
+/// the ventilator once the bathroom light has been on for 10 minutes
+#[derive(Debug, Clone)]
+pub struct Automation {
+    pub name: String,
+    pub trigger: Trigger,
+    pub commands: Vec<Command>
+}
@@ -0,0 +1,119 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use serde::Deserialize;
+
+// Always available so devices can be tried out without any hardware
+pub const LOG_BACKEND: &str = "log";
+
+/// Configuration of an execution backend as declared in the command map
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum BackendConfig {
+    Log,
+    Simulated,
+    Mqtt(MqttConfig),
+    HomeAssistant(HomeAssistantConfig),
+    Http(HttpConfig),
+    Shell(ShellConfig)
+}
+
+impl BackendConfig {
+    /// The device field a backend needs to find a device, if any
+    pub fn address_field(&self) -> Option<&'static str> {
+        match self {
+            BackendConfig::Log | BackendConfig::Simulated | BackendConfig::Shell(_) => None,
+            BackendConfig::Mqtt(_) => Some("topic"),
+            BackendConfig::HomeAssistant(_) => Some("entity_id"),
+            BackendConfig::Http(_) => Some("url")
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MqttConfig {
+    pub host: String,
+    #[serde(default = "default_mqtt_port")]
+    pub port: u16,
+    #[serde(default = "default_mqtt_client_id")]
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub tls: Option<MqttTlsConfig>,
+    #[serde(default)]
+    pub qos: u8,
+    #[serde(default)]
+    pub retain: bool,
+    // Payload templates by action label, they replace the defaults
+    #[serde(default)]
+    pub payloads: BTreeMap<String, String>
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MqttTlsConfig {
+    pub ca_file: PathBuf,
+    pub client_cert: Option<PathBuf>,
+    pub client_key: Option<PathBuf>
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HomeAssistantConfig {
+    pub url: String,
+    // A long-lived access token created on the Home Assistant profile page
+    pub token: String,
+    // Adds the entities assigned to areas as devices on startup
+    #[serde(default)]
+    pub import: bool,
+    // Which subject an imported entity becomes, by its domain
+    #[serde(default = "default_home_assistant_domains")]
+    pub domains: BTreeMap<String, String>
+}
+
+// The device's `url` is called, it can contain the same placeholders as the body
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HttpConfig {
+    #[serde(default = "default_http_method")]
+    pub method: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<String>,
+    #[serde(default = "default_timeout")]
+    pub timeout: u64
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShellConfig {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default = "default_timeout")]
+    pub timeout: u64
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_client_id() -> String {
+    "jarvis".to_string()
+}
+
+fn default_http_method() -> String {
+    "POST".to_string()
+}
+
+// In seconds
+fn default_timeout() -> u64 {
+    10
+}
+
+fn default_home_assistant_domains() -> BTreeMap<String, String> {
+    [("light", "light"), ("cover", "windowblinds"), ("climate", "temperature"), ("fan", "ventilator")]
+        .into_iter()
+        .map(|(domain, subject)| (domain.to_string(), subject.to_string()))
+        .collect()
+}
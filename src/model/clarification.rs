@@ -0,0 +1,62 @@
+use chrono::{DateTime, Local};
+
+use super::{command::Command, command_action::CommandAction, command_subject::CommandSubject};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CommandSlot {
+    Location, Action, Subject
+}
+
+/// A command where some of the slots couldn't be recognized (yet)
+#[derive(Debug, Clone, Default)]
+pub struct PartialCommand {
+    pub location: Option<String>,
+    pub action: Option<CommandAction>,
+    pub subject: Option<CommandSubject>
+}
+
+impl PartialCommand {
+    pub fn clear(mut self, slot: CommandSlot) -> Self {
+        match slot {
+            CommandSlot::Location => self.location = None,
+            CommandSlot::Action => self.action = None,
+            CommandSlot::Subject => self.subject = None
+        };
+
+        self
+    }
+
+    pub fn complete(self) -> Option<Command> {
+        match (self.location, self.action, self.subject) {
+            (Some(location), Some(action), Some(subject)) => Some(Command { location, action, subject }),
+            _ => None
+        }
+    }
+}
+
+impl From<Command> for PartialCommand {
+    fn from(command: Command) -> Self {
+        PartialCommand {
+            location: Some(command.location),
+            action: Some(command.action),
+            subject: Some(command.subject)
+        }
+    }
+}
+
+/// A possible answer to a clarification question
+#[derive(Debug, Clone)]
+pub struct SlotOption {
+    pub label: String,
+    pub name: String
+}
+
+/// A question we ask the user when exactly one slot of a command is unclear
+#[derive(Debug, Clone)]
+pub struct Clarification {
+    pub slot: CommandSlot,
+    pub options: Vec<SlotOption>,
+    pub pending: PartialCommand,
+    // When the command should run if a time was said with it, e.g. "in 20 minutes"
+    pub due: Option<DateTime<Local>>
+}
@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 use super::{command_action::CommandAction, command_subject::CommandSubject};
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Command { 
     pub location: String,
     pub action: CommandAction,
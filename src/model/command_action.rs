@@ -1,36 +1,83 @@
 use std::{fmt::Display, str::FromStr};
 
+use serde::{Deserialize, Serialize};
+
 use crate::traits::labelable::Labelable;
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum CommandSwitchValue {
-    On, Off
+    On, Off, Toggle
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum CommandGradientValue {
-    Min, Max, Less, More
+    Min, Max, Less, More,
+    // A concrete target in the subject's units, e.g. 21 degrees
+    Set(i32)
+}
+
+/// The kind of an action regardless of its value, as listed in the command map
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionKind {
+    Switch, Gradient
+}
+
+impl Display for ActionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Switch => write!(f, "switch"),
+            Self::Gradient => write!(f, "gradient")
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum CommandAction {
     Switch(CommandSwitchValue),
     Gradient(CommandGradientValue)
 }
 
 impl CommandAction {
-    pub fn from_command_parser(str: &str) -> Option<Self> {
-        match str {
-            "switch" => Some(Self::Switch(CommandSwitchValue::Off)),
-            "gradient" => Some(Self::Gradient(CommandGradientValue::Min)),
-            _ => None
+    pub fn kind(&self) -> ActionKind {
+        match self {
+            CommandAction::Switch(_) => ActionKind::Switch,
+            CommandAction::Gradient(_) => ActionKind::Gradient
         }
     }
 
     pub fn is_same_action(&self, other: &CommandAction) -> bool {
-        matches!((self, other), 
-            (CommandAction::Switch(_), CommandAction::Switch(_)) |
-            (CommandAction::Gradient(_), CommandAction::Gradient(_)))
+        self.kind() == other.kind()
+    }
+
+    /// The label the action is classified with, the opposite of `from_label`
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Switch(CommandSwitchValue::On) => "turn on",
+            Self::Switch(CommandSwitchValue::Off) => "turn off",
+            Self::Switch(CommandSwitchValue::Toggle) => "toggle",
+            Self::Gradient(CommandGradientValue::Min) => "close",
+            Self::Gradient(CommandGradientValue::Max) => "open",
+            Self::Gradient(CommandGradientValue::More) => "increase",
+            Self::Gradient(CommandGradientValue::Less) => "decrease",
+            Self::Gradient(CommandGradientValue::Set(_)) => "set"
+        }
+    }
+
+    /// Just the value, for scripts and webhooks that already know the action kind
+    pub fn value(&self) -> String {
+        let value = match self {
+            Self::Switch(CommandSwitchValue::On) => "on",
+            Self::Switch(CommandSwitchValue::Off) => "off",
+            Self::Switch(CommandSwitchValue::Toggle) => "toggle",
+            Self::Gradient(CommandGradientValue::Min) => "min",
+            Self::Gradient(CommandGradientValue::Max) => "max",
+            Self::Gradient(CommandGradientValue::More) => "more",
+            Self::Gradient(CommandGradientValue::Less) => "less",
+            Self::Gradient(CommandGradientValue::Set(value)) => return value.to_string()
+        };
+
+        value.to_string()
     }
 }
 
@@ -39,10 +86,12 @@ impl Display for CommandAction {
         let str = match self {
             Self::Switch(CommandSwitchValue::On) => "turned on",
             Self::Switch(CommandSwitchValue::Off) => "turned off",
+            Self::Switch(CommandSwitchValue::Toggle) => "toggled",
             Self::Gradient(CommandGradientValue::Min) => "closed",
             Self::Gradient(CommandGradientValue::Max) => "opened",
             Self::Gradient(CommandGradientValue::More) => "raised",
-            Self::Gradient(CommandGradientValue::Less) => "lowered"
+            Self::Gradient(CommandGradientValue::Less) => "lowered",
+            Self::Gradient(CommandGradientValue::Set(_)) => "set"
         };
 
         write!(f, "{}", str)
@@ -62,6 +111,7 @@ impl Labelable for CommandAction {
         match label {
             "switch" | "turn off" => Self::Switch(CommandSwitchValue::Off),
             "turn on" => Self::Switch(CommandSwitchValue::On),
+            "toggle" => Self::Switch(CommandSwitchValue::Toggle),
             "increase" => Self::Gradient(CommandGradientValue::More),
             "decrease" => Self::Gradient(CommandGradientValue::Less),
             "close" => Self::Gradient(CommandGradientValue::Min),
@@ -74,6 +124,7 @@ impl Labelable for CommandAction {
         vec![
             "turn on".to_string(),
             "turn off".to_string(),
+            "toggle".to_string(),
             "increase".to_string(),
             "decrease".to_string(),
             "close".to_string(),
@@ -1,6 +1,17 @@
-use super::command::Command;
+use std::collections::BTreeMap;
+
+use super::{aliases::Aliases, backend_config::BackendConfig, audio_source::AudioSource, automation::Automation, command::Command, command_subject::SubjectDefinition, device::Device, routine::Routine, scene::{LocationGroup, Scene}};
 
 #[derive(Debug)]
 pub struct CommandMap {
-    pub commands: Vec<Command>
+    pub commands: Vec<Command>,
+    pub subjects: Vec<SubjectDefinition>,
+    pub devices: Vec<Device>,
+    pub backends: BTreeMap<String, BackendConfig>,
+    pub sources: Vec<AudioSource>,
+    pub groups: Vec<LocationGroup>,
+    pub scenes: Vec<Scene>,
+    pub routines: Vec<Routine>,
+    pub automations: Vec<Automation>,
+    pub aliases: Aliases
 }
\ No newline at end of file
@@ -1,65 +1,91 @@
-use std::{fmt::Display, str::FromStr};
+use std::{fmt::Display, hash::Hash};
 
-use crate::traits::labelable::Labelable;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub enum CommandSubject {
-    Light, Teapot, WindowBlinds, Temperature, Ventilator
-}
+use super::command_action::ActionKind;
 
-impl CommandSubject {
-    pub fn from_command_parser(str: &str) -> Option<Self> {
-        Self::internal_from_str(str)
-    }
+/// Something an action can be performed upon. Subjects are compared by
+/// their label, the name is what we say out loud.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandSubject {
+    pub label: String,
+    pub name: String
+}
 
-    // Unified for several methods here
-    fn internal_from_str(str: &str) -> Option<Self> {
-        match str {
-            "light" => Some(Self::Light),
-            "teapot" => Some(Self::Teapot),
-            "windowblinds" => Some(Self::WindowBlinds),
-            "temperature" => Some(Self::Temperature),
-            "ventilator" => Some(Self::Ventilator),
-            _ => None
-        }
+impl PartialEq for CommandSubject {
+    fn eq(&self, other: &Self) -> bool {
+        self.label == other.label
     }
 }
 
-impl FromStr for CommandSubject {
-    type Err = ();
+impl Eq for CommandSubject {}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self::from_label(s))
+impl Hash for CommandSubject {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.label.hash(state);
     }
 }
 
 impl Display for CommandSubject {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Light => write!(f, "light"),
-            Self::Teapot => write!(f, "teapot"),
-            Self::WindowBlinds => write!(f, "window blinds"),
-            Self::Temperature => write!(f, "temperature"),
-            Self::Ventilator => write!(f, "ventilator")
-        }
+        write!(f, "{}", self.name)
     }
 }
 
-impl Labelable for CommandSubject {
-    fn from_label(label: &str) -> Self {
-        match Self::internal_from_str(label) {
-            Some(subject) => subject,
-            None => Self::Light
+/// Describes a kind of device. The defaults can be extended and new
+/// subjects added in the `subjects` section of the command map.
+#[derive(Debug, Clone)]
+pub struct SubjectDefinition {
+    pub label: String,
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub actions: Vec<ActionKind>,
+    // The range of the subject's level and how much "more" or "less"
+    // moves it. Percent unless the subject says otherwise.
+    pub step: i32,
+    pub min: i32,
+    pub max: i32,
+    pub unit: String
+}
+
+impl SubjectDefinition {
+    pub fn new(label: &str, name: &str, actions: &[ActionKind]) -> Self {
+        SubjectDefinition {
+            label: label.to_string(),
+            name: name.to_string(),
+            aliases: Vec::new(),
+            actions: actions.to_vec(),
+            step: 10,
+            min: 0,
+            max: 100,
+            unit: "percent".to_string()
         }
     }
 
-    fn labels() -> Vec<String> {
+    pub fn defaults() -> Vec<Self> {
         vec![
-            "light".to_string(),
-            "teapot".to_string(),
-            "windowblinds".to_string(),
-            "temperature".to_string(),
-            "ventilator".to_string()
+            Self::new("light", "light", &[ActionKind::Switch]),
+            Self::new("teapot", "teapot", &[ActionKind::Switch]),
+            Self::new("windowblinds", "window blinds", &[ActionKind::Gradient]),
+            // In degrees Celsius
+            SubjectDefinition { step: 1, min: 16, max: 28, unit: "degrees".to_string(), ..Self::new("temperature", "temperature", &[ActionKind::Gradient]) },
+            Self::new("ventilator", "ventilator", &[ActionKind::Switch])
         ]
     }
-}
\ No newline at end of file
+
+    /// Keeps a level within what the subject can do
+    pub fn clamp(&self, level: i32) -> i32 {
+        level.clamp(self.min, self.max)
+    }
+
+    pub fn subject(&self) -> CommandSubject {
+        CommandSubject {
+            label: self.label.clone(),
+            name: self.name.clone()
+        }
+    }
+
+    pub fn supports(&self, kind: ActionKind) -> bool {
+        self.actions.contains(&kind)
+    }
+}
@@ -0,0 +1,30 @@
+use super::command_subject::CommandSubject;
+
+/// How a backend finds the physical device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceAddress {
+    None,
+    Topic(String),
+    Url(String),
+    EntityId(String)
+}
+
+/// A physical device behind a location and subject pair. Several
+/// devices can share the pair, e.g. all the bulbs in a room.
+#[derive(Debug, Clone)]
+pub struct Device {
+    pub id: String,
+    pub location: String,
+    pub subject: CommandSubject,
+    pub backend: String,
+    pub address: DeviceAddress,
+    // Where the device reports its state, only for MQTT devices
+    pub state_topic: Option<String>
+}
+
+impl Device {
+    /// The id read out loud, e.g. "living room lamp" for `living-room-lamp`
+    pub fn spoken_name(&self) -> String {
+        self.id.replace(['-', '_', '.'], " ")
+    }
+}
@@ -0,0 +1,19 @@
+use super::{command_subject::CommandSubject, device::Device, device_state::DeviceState};
+
+/// A question about the state of devices, e.g. "is the bathroom ventilator on?"
+#[derive(Debug, Clone)]
+pub struct DeviceQuery {
+    pub location: String,
+    pub subject: CommandSubject
+}
+
+/// The answer from the state store. Devices we know nothing about have no state.
+#[derive(Debug)]
+pub struct QueryOutcome {
+    pub query: DeviceQuery,
+    pub devices: Vec<(Device, Option<DeviceState>)>,
+    // What the levels are measured in, e.g. "percent"
+    pub unit: String,
+    // Asked about a group or everywhere, so each device is answered about on its own
+    pub is_group: bool
+}
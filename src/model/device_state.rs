@@ -0,0 +1,43 @@
+use super::{command_action::{CommandAction, CommandGradientValue, CommandSwitchValue}, command_subject::SubjectDefinition};
+
+/// What we know about a device. Anything we haven't been told is None.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceState {
+    pub on: Option<bool>,
+    // In the subject's units, e.g. brightness in percent or degrees
+    pub level: Option<i32>
+}
+
+impl DeviceState {
+    /// The state after an action succeeded. Relative changes leave
+    /// the level unknown unless the device reports it.
+    pub fn after(self, action: &CommandAction, subject: &SubjectDefinition) -> Self {
+        match action {
+            CommandAction::Switch(CommandSwitchValue::On) => DeviceState { on: Some(true), ..self },
+            CommandAction::Switch(CommandSwitchValue::Off) => DeviceState { on: Some(false), ..self },
+            CommandAction::Switch(CommandSwitchValue::Toggle) => DeviceState { on: self.on.map(|on| !on), ..self },
+            CommandAction::Gradient(CommandGradientValue::Min) => DeviceState { on: Some(false), level: Some(subject.min) },
+            CommandAction::Gradient(CommandGradientValue::Max) => DeviceState { on: Some(true), level: Some(subject.max) },
+            CommandAction::Gradient(CommandGradientValue::Set(level)) => DeviceState { level: Some(*level), ..self },
+            CommandAction::Gradient(CommandGradientValue::More | CommandGradientValue::Less) => DeviceState { level: None, ..self }
+        }
+    }
+
+    pub fn is_known(&self) -> bool {
+        self.on.is_some() || self.level.is_some()
+    }
+}
+
+/// Reads the on/off states devices commonly report, e.g. "ON" or "closed"
+pub fn switch_state(state: &str) -> Option<bool> {
+    match state.to_lowercase().as_str() {
+        "on" | "open" | "true" => Some(true),
+        "off" | "close" | "closed" | "false" => Some(false),
+        _ => None
+    }
+}
+
+/// Converts a value on a device's own scale (e.g. brightness 0-254) to percent
+pub fn percent(value: f64, max: f64) -> i32 {
+    (value / max * 100.0).round().clamp(0.0, 100.0) as i32
+}
@@ -0,0 +1,107 @@
+use crate::errors::execution_error::ExecutionError;
+
+use super::{clarification::Clarification, command::Command, command_action::{CommandAction, CommandGradientValue}, device::Device, device_query::QueryOutcome, local_answer::LocalAnswer, reminder::ReminderOutcome, routine::RoutineOutcome, schedule::ScheduleOutcome, timer::TimerOutcome};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ExecutionStatus {
+    Success, PartialSuccess, Failure
+}
+
+#[derive(Debug)]
+pub struct DeviceOutcome {
+    pub device: Device,
+    // What the device was actually asked to do, e.g. "more" resolved to a level
+    pub action: CommandAction,
+    pub result: Result<(), ExecutionError>
+}
+
+/// What actually happened when a command was executed, device by device
+#[derive(Debug)]
+pub struct ExecutionOutcome {
+    pub command: Command,
+    pub devices: Vec<DeviceOutcome>,
+    // What the subject's levels are measured in, e.g. "degrees"
+    pub unit: String
+}
+
+impl ExecutionOutcome {
+    // A command without any devices behind it didn't do anything either
+    pub fn status(&self) -> ExecutionStatus {
+        status_of(self.succeeded().count(), self.devices.len())
+    }
+
+    pub fn succeeded(&self) -> impl Iterator<Item = &Device> {
+        self.devices
+            .iter()
+            .filter(|outcome| outcome.result.is_ok())
+            .map(|outcome| &outcome.device)
+    }
+
+    /// The level every device was set to, if they all ended up at the same one
+    pub fn level(&self) -> Option<i32> {
+        let mut levels = self.devices.iter().map(|outcome| match outcome.action {
+            CommandAction::Gradient(CommandGradientValue::Set(level)) if outcome.result.is_ok() => Some(level),
+            _ => None
+        });
+
+        let first = levels.next()??;
+        levels.all(|level| level == Some(first)).then_some(first)
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = (&Device, &ExecutionError)> {
+        self.devices
+            .iter()
+            .filter_map(|outcome| outcome.result.as_ref().err().map(|error| (&outcome.device, error)))
+    }
+}
+
+/// What a batch of commands was started by, used to describe it as a whole
+#[derive(Debug)]
+pub enum BatchSource {
+    Scene(String),
+    Group(String),
+    Routine { name: String, announcement: Option<String> },
+    Automation(String)
+}
+
+/// Several commands executed at once, e.g. for a scene, a routine or a group of locations
+#[derive(Debug)]
+pub struct BatchOutcome {
+    pub source: BatchSource,
+    pub outcomes: Vec<ExecutionOutcome>
+}
+
+impl BatchOutcome {
+    pub fn status(&self) -> ExecutionStatus {
+        let succeeded = self.outcomes.iter().map(|outcome| outcome.succeeded().count()).sum();
+        let total = self.outcomes.iter().map(|outcome| outcome.devices.len()).sum();
+
+        status_of(succeeded, total)
+    }
+}
+
+fn status_of(succeeded: usize, total: usize) -> ExecutionStatus {
+    if succeeded > 0 && succeeded == total {
+        ExecutionStatus::Success
+    } else if succeeded > 0 {
+        ExecutionStatus::PartialSuccess
+    } else {
+        ExecutionStatus::Failure
+    }
+}
+
+/// An intent after it went through the executor, handed over to the feedback generator
+#[derive(Debug)]
+pub enum ExecutedIntent {
+    Command(ExecutionOutcome),
+    Batch(BatchOutcome),
+    Question(String),
+    // A question answered without the question answering model
+    Answer(LocalAnswer),
+    Query(QueryOutcome),
+    Timer(TimerOutcome),
+    Schedule(ScheduleOutcome),
+    Routine(RoutineOutcome),
+    Reminder(ReminderOutcome),
+    Clarification(Clarification)
+}
@@ -1,17 +1,37 @@
 use crate::traits::labelable::Labelable;
 
-use super::command::Command;
+use super::{clarification::Clarification, command::Command, device_query::DeviceQuery, reminder::ReminderRequest, routine::RoutineRequest, schedule::ScheduleRequest, timer::TimerRequest};
 
 #[derive(Debug)]
 pub enum Intent {
     Command(Command),
-    Question(String)
+    // The name of a scene from the command map
+    Scene(String),
+    Question(String),
+    Query(DeviceQuery),
+    Timer(TimerRequest),
+    Schedule(ScheduleRequest),
+    Routine(RoutineRequest),
+    Reminder(ReminderRequest),
+    Clarification(Clarification)
 }
 
 impl Intent {
     pub fn is_label_question(label: &str) -> bool {
         label == "question"
     }
+
+    pub fn is_label_query(label: &str) -> bool {
+        label == "device status"
+    }
+
+    pub fn is_label_timer(label: &str) -> bool {
+        label == "timer"
+    }
+
+    pub fn is_label_schedule(label: &str) -> bool {
+        label == "schedule"
+    }
 }
 
 impl Labelable for Intent {
@@ -20,6 +40,6 @@ impl Labelable for Intent {
     }
 
     fn labels() -> Vec<String> {
-        vec!["command".to_string(), "question".to_string()]
+        vec!["command".to_string(), "question".to_string(), "device status".to_string(), "timer".to_string(), "schedule".to_string()]
     }
 }
\ No newline at end of file
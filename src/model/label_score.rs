@@ -0,0 +1,6 @@
+/// How well a label fits a text, from 0 to 1
+#[derive(Debug, Clone)]
+pub struct LabelScore {
+    pub text: String,
+    pub score: f64
+}
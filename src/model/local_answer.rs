@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+
+use super::device_query::DeviceQuery;
+
+/// A question with a definite answer that's answered without
+/// the question answering model
+#[derive(Debug, Clone)]
+pub enum LocalQuestion {
+    Time,
+    Date,
+    Weekday,
+    Uptime,
+    Timers,
+    DeviceState(DeviceQuery),
+    Capabilities
+}
+
+/// What the command map lets Jarvis do, for "what can you do?"
+#[derive(Debug)]
+pub struct Capabilities {
+    pub subjects: Vec<String>,
+    pub locations: Vec<String>,
+    pub scenes: Vec<String>
+}
+
+#[derive(Debug)]
+pub enum LocalAnswer {
+    Time(DateTime<Local>),
+    Date(DateTime<Local>),
+    Weekday(DateTime<Local>),
+    // How long Jarvis has been running
+    Uptime(Duration),
+    Capabilities(Capabilities)
+}
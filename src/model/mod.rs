@@ -2,4 +2,23 @@ pub mod command;
 pub mod command_action;
 pub mod command_subject;
 pub mod command_map;
-pub mod intent;
\ No newline at end of file
+pub mod intent;
+pub mod clarification;
+pub mod utterance;
+pub mod audio_source;
+pub mod aliases;
+pub mod device;
+pub mod backend_config;
+pub mod execution_outcome;
+pub mod device_state;
+pub mod device_query;
+pub mod scene;
+pub mod timer;
+pub mod speech_request;
+pub mod schedule;
+pub mod routine;
+pub mod reminder;
+pub mod automation;
+pub mod local_answer;
+pub mod settings;
+pub mod label_score;
\ No newline at end of file
@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// Something to be said at a given time. Kept on disk so it survives a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reminder {
+    pub id: u64,
+    // What to remind of as it was said, e.g. "to take the laundry out"
+    pub text: String,
+    pub due: DateTime<Local>,
+    // How often it went off without being snoozed or dismissed
+    #[serde(default)]
+    pub rings: u32
+}
+
+#[derive(Debug, Clone)]
+pub enum ReminderRequest {
+    Add { text: String, due: DateTime<Local> },
+    // Both act on every reminder that went off lately
+    Snooze(Duration),
+    Dismiss
+}
+
+#[derive(Debug)]
+pub enum ReminderOutcome {
+    Added(Reminder),
+    Snoozed(Vec<Reminder>),
+    Dismissed(Vec<Reminder>),
+    NothingRinging
+}
@@ -0,0 +1,53 @@
+use chrono::{DateTime, Datelike, Local, Timelike};
+
+use super::command::Command;
+
+/// When a routine runs, down to the minute. A field without
+/// values matches any of them, like a `*` in a crontab.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    pub minutes: Option<Vec<u32>>,
+    pub hours: Option<Vec<u32>>,
+    pub days_of_month: Option<Vec<u32>>,
+    pub months: Option<Vec<u32>>,
+    // Counted from Sunday as 0 like cron does
+    pub weekdays: Option<Vec<u32>>
+}
+
+impl Recurrence {
+    pub fn matches(&self, time: DateTime<Local>) -> bool {
+        let allows = |values: &Option<Vec<u32>>, value: u32| values.as_ref().is_none_or(|values| values.contains(&value));
+        let weekday = time.weekday().num_days_from_sunday();
+
+        // Like in cron, giving both a day of the month and a weekday means either of them
+        let is_day = match (&self.days_of_month, &self.weekdays) {
+            (Some(_), Some(_)) => allows(&self.days_of_month, time.day()) || allows(&self.weekdays, weekday),
+            _ => allows(&self.days_of_month, time.day()) && allows(&self.weekdays, weekday)
+        };
+
+        is_day && allows(&self.minutes, time.minute()) && allows(&self.hours, time.hour()) && allows(&self.months, time.month())
+    }
+}
+
+/// Commands that run by themselves on a schedule, e.g. raising the
+/// blinds every weekday morning. Said out loud when there's an announcement.
+#[derive(Debug, Clone)]
+pub struct Routine {
+    pub name: String,
+    pub recurrence: Recurrence,
+    pub commands: Vec<Command>,
+    pub announcement: Option<String>
+}
+
+#[derive(Debug, Clone)]
+pub enum RoutineRequest {
+    Pause(String),
+    Resume(String)
+}
+
+#[derive(Debug)]
+pub enum RoutineOutcome {
+    Paused(String),
+    Resumed(String),
+    NotFound(String)
+}
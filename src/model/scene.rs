@@ -0,0 +1,20 @@
+use super::command::Command;
+
+// Addresses every location that can do what was asked, e.g. "turn off all the lights".
+// A bare "all" isn't one of them, it would also match "turn it all off".
+pub const EVERYWHERE: &str = "everywhere";
+pub const EVERYWHERE_ALIASES: [&str; 2] = ["whole house", "every room"];
+
+/// A named set of commands that run together, e.g. "movie mode"
+#[derive(Debug, Clone)]
+pub struct Scene {
+    pub name: String,
+    pub commands: Vec<Command>
+}
+
+/// Several locations that can be addressed by one name, e.g. "downstairs"
+#[derive(Debug, Clone)]
+pub struct LocationGroup {
+    pub name: String,
+    pub locations: Vec<String>
+}
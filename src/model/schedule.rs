@@ -0,0 +1,27 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use super::{command::Command, command_subject::CommandSubject};
+
+/// A command waiting for its time to come. Kept on disk so it survives a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledCommand {
+    pub id: u64,
+    pub due: DateTime<Local>,
+    pub command: Command
+}
+
+#[derive(Debug, Clone)]
+pub enum ScheduleRequest {
+    Add { command: Command, due: DateTime<Local> },
+    List,
+    // Cancels everything scheduled that matches what was said
+    Cancel { location: Option<String>, subject: Option<CommandSubject> }
+}
+
+#[derive(Debug)]
+pub enum ScheduleOutcome {
+    Added(ScheduledCommand),
+    Listed(Vec<ScheduledCommand>),
+    Cancelled(Vec<ScheduledCommand>)
+}
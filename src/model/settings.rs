@@ -0,0 +1,93 @@
+use serde::Deserialize;
+
+/// Everything in settings.yaml. The file is optional and so is every setting in it.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    #[serde(default)]
+    pub classifier: ClassifierKind,
+    #[serde(default)]
+    pub question_answering: QuestionAnsweringConfig
+}
+
+/// What turns an instruction into an intent
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClassifierKind {
+    // BART, understands loose wording but needs about a gigabyte of memory
+    ZeroShot,
+    // Only the names and aliases from the command map, fits on small devices
+    Rules
+}
+
+// Builds without the models can't default to them
+impl Default for ClassifierKind {
+    fn default() -> Self {
+        if cfg!(feature = "transformers") { ClassifierKind::ZeroShot } else { ClassifierKind::Rules }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuestionAnsweringConfig {
+    #[serde(default)]
+    pub backend: AnswererConfig,
+    // Only chat models make use of it
+    #[serde(default = "default_system_prompt")]
+    pub system_prompt: String,
+    // In seconds
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+    // Answers are cut down to whole sentences that fit, nobody wants an essay read out
+    #[serde(default = "default_max_words")]
+    pub max_words: usize
+}
+
+impl Default for QuestionAnsweringConfig {
+    fn default() -> Self {
+        QuestionAnsweringConfig {
+            backend: AnswererConfig::default(),
+            system_prompt: default_system_prompt(),
+            timeout: default_timeout(),
+            max_words: default_max_words()
+        }
+    }
+}
+
+/// What answers the questions Jarvis can't answer by itself
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum AnswererConfig {
+    Gpt2,
+    #[serde(rename = "openai")]
+    OpenAi(OpenAiConfig),
+    Disabled
+}
+
+impl Default for AnswererConfig {
+    fn default() -> Self {
+        if cfg!(feature = "transformers") { AnswererConfig::Gpt2 } else { AnswererConfig::Disabled }
+    }
+}
+
+// Any server with an OpenAI style chat completions endpoint, e.g. llama.cpp or ollama
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OpenAiConfig {
+    // Up to and including the version, e.g. http://192.168.1.20:11434/v1
+    pub url: String,
+    pub model: String,
+    pub api_key: Option<String>
+}
+
+fn default_system_prompt() -> String {
+    "You are Jarvis, a voice assistant. Your answers are read out loud, so answer in one or two short sentences without any formatting.".to_string()
+}
+
+fn default_timeout() -> u64 {
+    20
+}
+
+fn default_max_words() -> usize {
+    40
+}
@@ -0,0 +1,9 @@
+/// Something for the speech synthesizer to play
+#[derive(Debug, Clone)]
+pub enum SpeechRequest {
+    Say(String),
+    // Rings the alarm before saying what it's for
+    Alarm(String),
+    // A softer sound than the alarm, for reminders
+    Chime(String)
+}
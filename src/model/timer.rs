@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+/// Something the user asked about timers. Timers without a name are
+/// fine as long as there's only one of them.
+#[derive(Debug, Clone)]
+pub enum TimerRequest {
+    Start { name: Option<String>, duration: Duration },
+    Cancel { name: Option<String> },
+    TimeLeft { name: Option<String> }
+}
+
+#[derive(Debug, Clone)]
+pub struct TimerInfo {
+    pub name: Option<String>,
+    pub remaining: Duration
+}
+
+/// What the timer scheduler did with a request
+#[derive(Debug)]
+pub enum TimerOutcome {
+    Started(TimerInfo),
+    Cancelled(TimerInfo),
+    TimeLeft(Vec<TimerInfo>),
+    NotFound(Option<String>),
+    // Several timers are running and the request didn't say which one
+    Ambiguous(Vec<TimerInfo>)
+}
@@ -0,0 +1,6 @@
+/// Recognized speech together with the audio source it came from
+#[derive(Debug)]
+pub struct Utterance {
+    pub source: String,
+    pub text: String
+}
@@ -1,67 +1,147 @@
-use std::sync::mpsc::{Receiver, Sender};
+use std::{sync::{mpsc::{Receiver, RecvTimeoutError, Sender}, Arc}, time::Duration};
 
 use anyhow::Result;
-use rust_bert::{pipelines::{sequence_classification::Label, zero_shot_classification::{ZeroShotClassificationConfig, ZeroShotClassificationModel}}, RustBertError};
+use chrono::{DateTime, Local};
 
-use crate::{core::commander::Commander, model::{command::Command, command_action::CommandAction, command_subject::CommandSubject, intent::Intent}, traits::labelable::Labelable};
+use crate::{core::{commander::Commander, dialogue::Dialogue, shared_commander::SharedCommander, spoken_time::{due_time_at, parse_duration, spoken_number, trailing_due_time, words}}, model::{clarification::{Clarification, CommandSlot, PartialCommand, SlotOption}, command::Command, command_action::CommandAction, command_subject::CommandSubject, device_query::DeviceQuery, intent::Intent, label_score::LabelScore, reminder::ReminderRequest, routine::RoutineRequest, scene::EVERYWHERE, schedule::ScheduleRequest, settings::ClassifierKind, timer::TimerRequest, utterance::Utterance}, scorers::rule_scorer::RuleScorer, traits::{label_scorer::LabelScorer, labelable::Labelable}};
+#[cfg(feature = "transformers")]
+use crate::scorers::zero_shot_scorer::ZeroShotScorer;
 
 pub type ClassifierOutput = Result<Intent, ClassificationFailureReason>;
 
 const SCORE_THRESHOLD: f64 = 0.85;
 
+// We don't want to read out the entire house when asking which room
+const MAX_CLARIFICATION_OPTIONS: usize = 3;
+
+// How often we check whether a pending clarification went stale
+// or the command map changed
+const DIALOGUE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+const CANCEL_WORDS: [&str; 4] = ["cancel", "stop", "delete", "remove"];
+const TIMER_TIME_LEFT_WORDS: [&str; 3] = ["long", "left", "remaining"];
+// Joins "remind me" and what to remind of, e.g. "remind me to" or "remind me that"
+const REMINDER_CONNECTORS: [&str; 3] = ["to", "that", "about"];
+const DEFAULT_SNOOZE: Duration = Duration::from_secs(10 * 60);
+
+// Said back to the user, so "my pills" becomes "your pills"
+const REMINDER_PRONOUNS: [(&str, &str); 6] = [
+    ("i", "you"), ("me", "you"), ("my", "your"), ("mine", "yours"), ("myself", "yourself"), ("i'm", "you're")
+];
+
+const ROUTINE_PAUSE_WORDS: [&str; 6] = ["pause", "disable", "stop", "suspend", "skip", "off"];
+const ROUTINE_RESUME_WORDS: [&str; 6] = ["resume", "enable", "unpause", "restart", "continue", "on"];
+
+// Words in front of "timer" that aren't part of its name, e.g. "set a ten minute timer"
+const TIMER_NAME_STOP_WORDS: [&str; 19] = [
+    "a", "an", "the", "my", "set", "start", "new", "this", "that", "on", "for", "of", "is", "cancel", "stop", "delete", "remove",
+    "minute", "hour"
+];
+
 struct ClassificationLabels {
     intents: Vec<String>,
     locations: Vec<String>,
     actions: Vec<String>,
-    subjects: Vec<String>
+    subjects: Vec<String>,
+    scenes: Vec<String>
+}
+
+impl ClassificationLabels {
+    fn all(&self) -> Vec<&str> {
+        self.locations
+            .iter()
+            .chain(self.actions.iter())
+            .chain(self.subjects.iter())
+            .chain(self.scenes.iter())
+            .chain(self.intents.iter())
+            .map(|s| s.as_str())
+            .collect()
+    }
+}
+
+struct CommandScores {
+    location: f64,
+    action: f64,
+    subject: f64
+}
+
+impl CommandScores {
+    fn min(&self) -> f64 {
+        self.action.min(self.location).min(self.subject)
+    }
+
+    // A slot filled in from configuration is as certain as it gets
+    fn assume(&mut self, slot: CommandSlot) {
+        match slot {
+            CommandSlot::Location => self.location = 1.0,
+            CommandSlot::Action => self.action = 1.0,
+            CommandSlot::Subject => self.subject = 1.0
+        };
+    }
+
+    fn unclear_slots(&self) -> Vec<CommandSlot> {
+        [
+            (CommandSlot::Location, self.location),
+            (CommandSlot::Action, self.action),
+            (CommandSlot::Subject, self.subject)
+        ]
+        .into_iter()
+        .filter(|(_, score)| *score < SCORE_THRESHOLD)
+        .map(|(slot, _)| slot)
+        .collect()
+    }
+}
+
+enum Classification {
+    Question(Intent),
+    Scene(Intent),
+    Timer(Intent),
+    Schedule(Intent),
+    Query(DeviceQuery, CommandScores),
+    Command(Command, CommandScores),
+    Unrecognized
 }
 
 pub enum ClassificationFailureReason {
     Unknown, UnsupportedInstruction, UnrecognizedInstruction
 }
 
-pub fn main(command_rx: Receiver<String>, intent_tx: Sender<ClassifierOutput>) -> Result<()> {
-    let model = load_model()?;
-    let commander = Commander::new();
-    let labels = build_labels(&commander);
-    let model_labels: Vec<&str> = labels.locations
-        .iter()
-        .chain(labels.actions.iter())
-        .chain(labels.subjects.iter())
-        .chain(labels.intents.iter())
-        .map(|s| s.as_str())
-        .collect();
+pub fn main(
+    kind: ClassifierKind,
+    commanders: Arc<SharedCommander>,
+    command_rx: Receiver<Utterance>,
+    intent_tx: Sender<ClassifierOutput>
+) -> Result<()> {
+    let scorer = load_scorer(kind)?;
+    let mut commander = commanders.current();
+    let mut labels = build_labels(&commander);
+    let mut dialogue = Dialogue::new();
 
-    while let Ok(instruction) = command_rx.recv() {
-        let inputs = vec![instruction.as_str()];
-        let output = match model.predict_multilabel(inputs, &model_labels, None, 128) {
-            Ok(result) => result,
-            Err(_) => {
-                if intent_tx.send(Err(ClassificationFailureReason::Unknown)).is_err() {
-                    break;
-                }
+    loop {
+        // Reloading the models takes ages so we only swap out the command map
+        let latest = commanders.current();
+        if !Arc::ptr_eq(&latest, &commander) {
+            commander = latest;
+            labels = build_labels(&commander);
+        }
+
+        let utterance = match command_rx.recv_timeout(DIALOGUE_POLL_INTERVAL) {
+            Ok(utterance) => utterance,
+            Err(RecvTimeoutError::Timeout) => {
+                dialogue.expire();
                 continue;
             }
+            Err(RecvTimeoutError::Disconnected) => break
         };
-        
-        let (intent, score) = intent_from_classification(&instruction, &output[0], &labels);
-        let result: Result<Intent, ClassificationFailureReason>;
-
-        if score < SCORE_THRESHOLD {
-            println!("Instruction '{}'\nScore {} with output: {:?}\n", instruction, score, output[0]);
-            result = Err(ClassificationFailureReason::UnrecognizedInstruction);
-        } else if let Intent::Command(ref command) = intent {
-            if commander.supports_command(command) {
-                println!("Instruction '{}'\nExecuting {:?}", instruction, intent);
-                result = Ok(intent);
-            } else {
-                result = Err(ClassificationFailureReason::UnsupportedInstruction);
-            }
-        } else if let Intent::Question(_) = intent {
-            result = Ok(intent);
-        } else { // Shouldn't really happen
-            println!("No suitable command for '{}'\n", instruction);
-            result = Err(ClassificationFailureReason::UnsupportedInstruction);
+
+        let result = match dialogue.take_pending() {
+            Some(clarification) => resolve_clarification(&utterance.text, clarification, scorer.as_ref(), &commander),
+            None => classify_instruction(&utterance, scorer.as_ref(), &labels, &commander)
+        };
+
+        if let Ok(Intent::Clarification(ref clarification)) = result {
+            println!("Instruction '{}'\nAsking about {:?}", utterance.text, clarification);
+            dialogue.await_clarification(clarification.clone());
         }
 
         if intent_tx.send(result).is_err() {
@@ -72,55 +152,548 @@ pub fn main(command_rx: Receiver<String>, intent_tx: Sender<ClassifierOutput>) -
     Ok(())
 }
 
-fn load_model() -> Result<ZeroShotClassificationModel, RustBertError> {
-    let config = ZeroShotClassificationConfig {
-        model_type: rust_bert::pipelines::common::ModelType::Bart,
-        ..Default::default()
+fn load_scorer(kind: ClassifierKind) -> Result<Box<dyn LabelScorer>> {
+    let scorer: Box<dyn LabelScorer> = match kind {
+        #[cfg(feature = "transformers")]
+        ClassifierKind::ZeroShot => Box::new(ZeroShotScorer::new()?),
+        #[cfg(not(feature = "transformers"))]
+        ClassifierKind::ZeroShot => return Err(anyhow::anyhow!("The `zero_shot` classifier needs the `transformers` feature, use `rules` instead")),
+        ClassifierKind::Rules => Box::new(RuleScorer)
     };
-    let model = ZeroShotClassificationModel::new(config)?;
+    println!("Classifying with {:?}", kind);
 
-    Ok(model)
+    Ok(scorer)
 }
 
 fn build_labels(commander: &Commander) -> ClassificationLabels {
+    let aliases = &commander.aliases;
+
     ClassificationLabels {
         intents: Intent::labels(),
-        locations: commander.locations.clone(),
-        actions: CommandAction::labels(),
-        subjects: CommandSubject::labels()
+        locations: commander.locations
+            .iter()
+            .chain(aliases.locations.aliases())
+            .chain(commander.groups.iter().map(|group| &group.name))
+            .cloned()
+            .chain([EVERYWHERE.to_string()])
+            .collect(),
+        actions: CommandAction::labels().into_iter().chain(aliases.actions.aliases().cloned()).collect(),
+        subjects: commander.subject_labels().into_iter().chain(aliases.subjects.aliases().cloned()).collect(),
+        scenes: commander.scenes.iter().map(|scene| scene.name.clone()).collect()
+    }
+}
+
+fn classify_instruction(
+    utterance: &Utterance,
+    scorer: &dyn LabelScorer,
+    labels: &ClassificationLabels,
+    commander: &Commander
+) -> ClassifierOutput {
+    let instruction = utterance.text.as_str();
+
+    // Routines are called by their name and reminders have telltale words, there's no need to ask the model
+    if let Some(request) = routine_request(instruction, commander) {
+        println!("Instruction '{}'\nRunning {:?}", instruction, request);
+        return Ok(Intent::Routine(request));
+    }
+    if let Some(request) = reminder_request(instruction) {
+        println!("Instruction '{}'\nRunning {:?}", instruction, request);
+        return Ok(Intent::Reminder(request));
+    }
+
+    // "turn off the light in 20 minutes" is run later. Only a time at the very end counts,
+    // and it's left out of what's classified so its numbers aren't taken for anything else.
+    let said = words(instruction);
+    let (command_text, due) = match trailing_due_time(&said, Local::now()) {
+        Some((start, due)) => (said[..start].join(" "), Some(due)),
+        None => (instruction.to_string(), None)
+    };
+
+    let output = match scorer.score(&command_text, &labels.all()) {
+        Ok(result) => result,
+        Err(_) => return Err(ClassificationFailureReason::Unknown)
+    };
+
+    let (mut command, mut scores) = match intent_from_classification(instruction, &output, labels, commander, due.is_some()) {
+        Classification::Question(intent) => return Ok(intent),
+        Classification::Scene(intent) | Classification::Timer(intent) | Classification::Schedule(intent) => {
+            println!("Instruction '{}'\nRunning {:?}", instruction, intent);
+            return Ok(intent);
+        }
+        Classification::Query(query, scores) => return query_intent(utterance, query, scores, commander),
+        Classification::Command(command, scores) => (command, scores),
+        Classification::Unrecognized => return Err(ClassificationFailureReason::UnrecognizedInstruction)
+    };
+
+    // Most of the time people don't name the room they're standing in
+    if scores.location < SCORE_THRESHOLD {
+        if let Some(location) = home_location_for(utterance, &command, commander) {
+            println!(
+                "Location score {:.2} for '{}' is too low, defaulting to '{}' of source '{}'",
+                scores.location, command.location, location, utterance.source
+            );
+            command.location = location;
+            scores.assume(CommandSlot::Location);
+        }
+    }
+
+    if scores.min() >= SCORE_THRESHOLD {
+        return if commander.supports_command(&command) {
+            println!("Instruction '{}'", instruction);
+            Ok(command_intent(command, due))
+        } else {
+            println!("No suitable command for '{}'\n", instruction);
+            Err(ClassificationFailureReason::UnsupportedInstruction)
+        };
+    }
+
+    // If only a single slot is unclear it's worth asking about it,
+    // otherwise we most likely misheard the whole thing
+    match scores.unclear_slots()[..] {
+        [slot] => clarify_slot(slot, command, due, &output, commander),
+        _ => {
+            println!("Instruction '{}'\nScore {} with output: {:?}\n", instruction, scores.min(), output);
+            Err(ClassificationFailureReason::UnrecognizedInstruction)
+        }
+    }
+}
+
+// There's no point in asking which room for a status question, the
+// answer would take longer than simply asking again
+fn query_intent(utterance: &Utterance, mut query: DeviceQuery, mut scores: CommandScores, commander: &Commander) -> ClassifierOutput {
+    if scores.location < SCORE_THRESHOLD {
+        let home_location = commander
+            .home_location(&utterance.source)
+            .filter(|location| commander.commands.iter().any(|command| command.location == **location && command.subject == query.subject));
+
+        if let Some(location) = home_location {
+            query.location = location.clone();
+            scores.assume(CommandSlot::Location);
+        }
+    }
+
+    if scores.location.min(scores.subject) < SCORE_THRESHOLD {
+        println!("Status question '{}'\nScore {} too low for {:?}\n", utterance.text, scores.location.min(scores.subject), query);
+        return Err(ClassificationFailureReason::UnrecognizedInstruction);
+    }
+
+    println!("Status question '{}'\nLooking up {:?}", utterance.text, query);
+    Ok(Intent::Query(query))
+}
+
+fn home_location_for(utterance: &Utterance, command: &Command, commander: &Commander) -> Option<String> {
+    let location = commander.home_location(&utterance.source)?;
+    let candidate = Command {
+        location: location.clone(),
+        action: command.action,
+        subject: command.subject.clone()
+    };
+
+    // If the home location can't do it the user most likely meant
+    // a different room so we'd rather ask about it
+    if commander.supports_command(&candidate) {
+        Some(location.clone())
+    } else {
+        None
     }
 }
 
-fn intent_from_classification(instruction: &str, model_output: &Vec<Label>, data: &ClassificationLabels) -> (Intent, f64) {
+fn intent_from_classification(
+    instruction: &str,
+    model_output: &[LabelScore],
+    data: &ClassificationLabels,
+    commander: &Commander,
+    has_due_time: bool
+) -> Classification {
     let mut action: (f64, usize) = (0.0, 0);
     let mut location: (f64, usize) = (0.0, 0);
+    let mut room: (f64, usize) = (0.0, 0);
     let mut subject: (f64, usize) = (0.0, 0);
+    let mut scene: (f64, usize) = (0.0, 0);
+    let mut question = 0.0;
+    let mut query = 0.0;
+    let mut timer = 0.0;
+    let mut schedule = 0.0;
 
     for (i, label) in model_output.iter().enumerate() {
         let score = label.score;
 
         // Figuring out if it's a question is a bit special
-        if data.intents.contains(&label.text) && score > SCORE_THRESHOLD {
-            // Only in the case of a recognized question we return early
+        if data.intents.contains(&label.text) {
             if Intent::is_label_question(&label.text) {
-                return (Intent::Question(instruction.to_string()), score);
+                question = score;
+            } else if Intent::is_label_query(&label.text) {
+                query = score;
+            } else if Intent::is_label_timer(&label.text) {
+                timer = score;
+            } else if Intent::is_label_schedule(&label.text) {
+                schedule = score;
+            }
+        } else if data.scenes.contains(&label.text) {
+            if score > scene.0 {
+                scene = (score, i);
             }
         } else if data.actions.contains(&label.text) && score > action.0 {
             action = (score, i);
         } else if data.subjects.contains(&label.text) && score > subject.0 {
             subject = (score, i);
-        } else if score > location.0 { // it's a location
-            location = (score, i);
+        } else { // it's a location
+            if score > location.0 {
+                location = (score, i);
+            }
+            if score > room.0 && commander.aliases.locations.canonical(&label.text) != EVERYWHERE {
+                room = (score, i);
+            }
+        }
+    }
+
+    // A room that was named wins over "all the lights"
+    if room.0 > SCORE_THRESHOLD {
+        location = room;
+    }
+
+    // Asking about one of our own devices is answered from what we know about
+    // it, everything else goes to the question answering model
+    let is_query = query > SCORE_THRESHOLD && query >= question;
+
+    // Naming a scene is enough, it already says what to do where
+    if scene.0 > SCORE_THRESHOLD && scene.0 > question.max(query) {
+        return Classification::Scene(Intent::Scene(model_output[scene.1].text.clone()));
+    }
+
+    if timer > SCORE_THRESHOLD && timer > question.max(query) {
+        if let Some(request) = timer_request(instruction) {
+            return Classification::Timer(Intent::Timer(request));
         }
     }
 
-    let score = action.0.min(location.0).min(subject.0);
+    if question > SCORE_THRESHOLD && !is_query {
+        return Classification::Question(Intent::Question(instruction.to_string()));
+    }
+
+    let scores = CommandScores {
+        location: location.0,
+        action: action.0,
+        subject: subject.0
+    };
+    // Aliases are only there to help the model, the command always uses canonical names
+    let aliases = &commander.aliases;
+    let subject = match commander.subject(aliases.subjects.canonical(&model_output[subject.1].text)) {
+        Some(subject) => subject,
+        None => return Classification::Unrecognized
+    };
+    let location = aliases.locations.canonical(&model_output[location.1].text).to_string();
+
+    if schedule > SCORE_THRESHOLD && schedule > question.max(query) {
+        if let Some(request) = schedule_request(instruction, &location, &subject, &scores, has_due_time) {
+            return Classification::Schedule(Intent::Schedule(request));
+        }
+    }
+
+    if is_query {
+        return Classification::Query(DeviceQuery { location, subject }, scores);
+    }
+
     let command = Command {
-        location: model_output[location.1].text.clone(),
-        action: model_output[action.1].text.parse::<CommandAction>().unwrap(),
-        subject: model_output[subject.1].text.parse::<CommandSubject>().unwrap()
+        location,
+        action: aliases.actions.canonical(&model_output[action.1].text).parse::<CommandAction>().unwrap(),
+        subject
     };
-    let intent = Intent::Command(command);
 
-    (intent, score)
-}
\ No newline at end of file
+    Classification::Command(command, scores)
+}
+
+// A command said with a time is scheduled for then instead of run right away
+fn command_intent(command: Command, due: Option<DateTime<Local>>) -> Intent {
+    match due {
+        Some(due) => {
+            println!("Scheduling {:?} for {}", command, due);
+            Intent::Schedule(ScheduleRequest::Add { command, due })
+        }
+        None => {
+            println!("Executing {:?}", command);
+            Intent::Command(command)
+        }
+    }
+}
+
+fn clarify_slot(
+    slot: CommandSlot,
+    command: Command,
+    due: Option<DateTime<Local>>,
+    model_output: &[LabelScore],
+    commander: &Commander
+) -> ClassifierOutput {
+    let pending = PartialCommand::from(command).clear(slot);
+
+    // An option scores as well as its best scoring alias
+    let alias_map = commander.aliases.for_slot(slot);
+    let score_of = |option: &SlotOption| model_output
+        .iter()
+        .filter(|label| alias_map.canonical(&label.text) == option.label)
+        .map(|label| label.score)
+        .fold(0.0, f64::max);
+
+    let mut options = commander.options_for_slot(slot, &pending);
+    options.sort_by(|a, b| score_of(b).total_cmp(&score_of(a)));
+    options.truncate(MAX_CLARIFICATION_OPTIONS);
+
+    match options.len() {
+        0 => Err(ClassificationFailureReason::UnsupportedInstruction),
+        1 => {
+            // No point in asking if there's only one sensible answer
+            let command = commander
+                .fill_slot(pending, slot, &options[0].label)
+                .complete()
+                .ok_or(ClassificationFailureReason::Unknown)?;
+
+            println!("Only one option for {:?}", slot);
+            Ok(command_intent(command, due))
+        }
+        _ => Ok(Intent::Clarification(Clarification { slot, options, pending, due }))
+    }
+}
+
+fn resolve_clarification(
+    answer: &str,
+    clarification: Clarification,
+    scorer: &dyn LabelScorer,
+    commander: &Commander
+) -> ClassifierOutput {
+    let label = match mentioned_option(answer, &clarification, commander) {
+        Some(label) => label,
+        None => {
+            let option_labels: Vec<&str> = clarification.options.iter().map(|option| option.label.as_str()).collect();
+            let output = scorer
+                .score(answer, &option_labels)
+                .map_err(|_| ClassificationFailureReason::Unknown)?;
+
+            match output.iter().max_by(|a, b| a.score.total_cmp(&b.score)) {
+                Some(label) if label.score >= SCORE_THRESHOLD => label.text.clone(),
+                _ => {
+                    println!("Could not match answer '{}' to any of {:?}\n", answer, clarification.options);
+                    return Err(ClassificationFailureReason::UnrecognizedInstruction);
+                }
+            }
+        }
+    };
+
+    let command = commander
+        .fill_slot(clarification.pending, clarification.slot, &label)
+        .complete()
+        .ok_or(ClassificationFailureReason::Unknown)?;
+
+    if commander.supports_command(&command) {
+        println!("Answer '{}'", answer);
+        Ok(command_intent(command, clarification.due))
+    } else {
+        Err(ClassificationFailureReason::UnsupportedInstruction)
+    }
+}
+
+// Most answers simply repeat one of the options so we try
+// to avoid running the model if we can
+fn mentioned_option(answer: &str, clarification: &Clarification, commander: &Commander) -> Option<String> {
+    let answer = answer.to_lowercase();
+    let alias_map = commander.aliases.for_slot(clarification.slot);
+
+    clarification.options
+        .iter()
+        .flat_map(|option| {
+            [option.label.clone(), option.name.clone()]
+                .into_iter()
+                .chain(alias_map.aliases_of(&option.label).cloned())
+                .map(move |name| (option, name))
+        })
+        .filter(|(_, name)| answer.contains(name.as_str()))
+        .max_by_key(|(_, name)| name.len())
+        .map(|(option, _)| option.label.clone())
+}
+
+// Listing or cancelling what's scheduled. A command with a time attached is
+// a new one to schedule and is left to the usual command handling.
+fn schedule_request(instruction: &str, location: &str, subject: &CommandSubject, scores: &CommandScores, has_due_time: bool) -> Option<ScheduleRequest> {
+    let words = words(instruction);
+
+    if words.iter().any(|word| CANCEL_WORDS.contains(&word.as_str())) {
+        Some(ScheduleRequest::Cancel {
+            location: (scores.location >= SCORE_THRESHOLD).then(|| location.to_string()),
+            subject: (scores.subject >= SCORE_THRESHOLD).then(|| subject.clone())
+        })
+    } else if has_due_time {
+        None
+    } else {
+        Some(ScheduleRequest::List)
+    }
+}
+
+// "pause the morning routine" or "turn the morning routine back on"
+fn routine_request(instruction: &str, commander: &Commander) -> Option<RoutineRequest> {
+    let words = words(instruction);
+    if !words.iter().any(|word| word == "routine" || word == "routines") {
+        return None;
+    }
+
+    let text = words.join(" ");
+    let name = commander.routines
+        .iter()
+        .map(|routine| &routine.name)
+        .filter(|name| text.contains(name.as_str()))
+        .max_by_key(|name| name.len())?
+        .clone();
+    let mentions = |candidates: &[&str]| words.iter().any(|word| candidates.contains(&word.as_str()));
+
+    if mentions(&ROUTINE_PAUSE_WORDS) {
+        Some(RoutineRequest::Pause(name))
+    } else if mentions(&ROUTINE_RESUME_WORDS) {
+        Some(RoutineRequest::Resume(name))
+    } else {
+        None
+    }
+}
+
+// "remind me to take the laundry out at 3", "snooze for five minutes" or "dismiss the reminder"
+fn reminder_request(instruction: &str) -> Option<ReminderRequest> {
+    let words = words(instruction);
+    let mentions = |candidates: &[&str]| words.iter().any(|word| candidates.contains(&word.as_str()));
+
+    if mentions(&["snooze"]) {
+        return Some(ReminderRequest::Snooze(parse_duration(instruction).unwrap_or(DEFAULT_SNOOZE)));
+    }
+    if mentions(&["dismiss"]) || (mentions(&["reminder"]) && mentions(&CANCEL_WORDS)) {
+        return Some(ReminderRequest::Dismiss);
+    }
+
+    let start = words.windows(2).position(|pair| pair[0] == "remind" && pair[1] == "me")? + 2;
+    let rest = &words[start..];
+    let now = Local::now();
+    let (at, due) = (0..rest.len()).find_map(|index| due_time_at(&rest[index..], now).map(|due| (index, due)))?;
+
+    // The time can be said before or after what to remind of
+    let text = match at {
+        0 => {
+            let connector = rest.iter().position(|word| REMINDER_CONNECTORS.contains(&word.as_str()))?;
+            &rest[connector..]
+        }
+        _ => &rest[..at]
+    };
+    if text.is_empty() {
+        return None;
+    }
+
+    let text = text
+        .iter()
+        .map(|word| REMINDER_PRONOUNS.iter().find(|(said, _)| said == word).map_or(word.as_str(), |(_, reply)| reply))
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    Some(ReminderRequest::Add { text, due })
+}
+
+// Timers are simple enough to be picked apart without the model
+fn timer_request(instruction: &str) -> Option<TimerRequest> {
+    let words = words(instruction);
+    if !words.iter().any(|word| word == "timer" || word == "timers") {
+        return None;
+    }
+
+    let name = timer_name(&words);
+    let mentions = |candidates: &[&str]| words.iter().any(|word| candidates.contains(&word.as_str()));
+
+    if mentions(&CANCEL_WORDS) {
+        Some(TimerRequest::Cancel { name })
+    } else if mentions(&TIMER_TIME_LEFT_WORDS) {
+        Some(TimerRequest::TimeLeft { name })
+    } else {
+        parse_duration(instruction).map(|duration| TimerRequest::Start { name, duration })
+    }
+}
+
+// "the pasta timer" or "a timer called pasta"
+fn timer_name(words: &[String]) -> Option<String> {
+    if let Some(index) = words.iter().position(|word| word == "called" || word == "named") {
+        let name = words[index + 1..].join(" ");
+        return (!name.is_empty()).then_some(name);
+    }
+
+    let index = words.iter().position(|word| word == "timer" || word == "timers")?;
+    let name: Vec<&str> = words[..index]
+        .iter()
+        .rev()
+        .take_while(|word| !TIMER_NAME_STOP_WORDS.contains(&word.as_str()) && spoken_number(word).is_none())
+        .take(2)
+        .map(String::as_str)
+        .collect();
+
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.into_iter().rev().collect::<Vec<&str>>().join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{core::command_map_parser::parse_command_map, model::command_action::CommandSwitchValue};
+
+    use super::*;
+
+    const COMMAND_MAP: &str = "\
+version: 1
+locations:
+  kitchen:
+    switch:
+      - light
+  bathroom:
+    switch:
+      - light
+      - ventilator
+";
+
+    fn commander(name: &str) -> Commander {
+        let file = std::env::temp_dir().join(format!("jarvis-test-{}-{}.yaml", name, std::process::id()));
+        std::fs::write(&file, COMMAND_MAP).unwrap();
+        let (map, locations) = parse_command_map(&file.to_string_lossy()).unwrap();
+        let _ = std::fs::remove_file(&file);
+
+        Commander::new(map, locations)
+    }
+
+    fn said(text: &str) -> Utterance {
+        Utterance { source: "default".to_string(), text: text.to_string() }
+    }
+
+    #[test]
+    fn a_timed_command_keeps_its_time_through_a_clarification() {
+        let commander = commander("clarified-schedule");
+        let labels = build_labels(&commander);
+
+        let clarification = match classify_instruction(&said("turn off the light in 20 minutes"), &RuleScorer, &labels, &commander) {
+            Ok(Intent::Clarification(clarification)) => clarification,
+            other => panic!("Expected a clarification, got {:?}", other.ok())
+        };
+        assert_eq!(clarification.slot, CommandSlot::Location);
+        let asked_due = clarification.due.expect("the time should be kept");
+
+        match resolve_clarification("the bathroom", clarification, &RuleScorer, &commander) {
+            Ok(Intent::Schedule(ScheduleRequest::Add { command, due })) => {
+                assert_eq!(command.location, "bathroom");
+                assert_eq!(command.action, CommandAction::Switch(CommandSwitchValue::Off));
+                assert_eq!(due, asked_due);
+            }
+            other => panic!("Expected the command to be scheduled, got {:?}", other.ok())
+        }
+    }
+
+    #[test]
+    fn the_only_option_is_scheduled_when_a_time_was_said() {
+        let commander = commander("only-option-schedule");
+        let labels = build_labels(&commander);
+
+        // Only the bathroom has a ventilator so there's nothing to ask
+        match classify_instruction(&said("turn on the ventilator in 5 minutes"), &RuleScorer, &labels, &commander) {
+            Ok(Intent::Schedule(ScheduleRequest::Add { command, .. })) => assert_eq!(command.location, "bathroom"),
+            other => panic!("Expected the command to be scheduled, got {:?}", other.ok())
+        }
+    }
+}
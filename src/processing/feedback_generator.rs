@@ -1,34 +1,31 @@
-use std::sync::mpsc::{Receiver, Sender};
+use std::{sync::mpsc::{Receiver, Sender}, time::Duration};
 
 use anyhow::Result;
+use chrono::{DateTime, Local};
 use rand::Rng;
-use rust_bert::{gpt2::GPT2Generator, pipelines::generation_utils::{GenerateConfig, LanguageGenerator}};
-
-use crate::model::{command::Command, intent::Intent};
-
-use super::classifier::ClassificationFailureReason;
-
-pub fn main(intent_rx: Receiver<Result<Intent, ClassificationFailureReason>>, feedback_tx: Sender<String>) -> Result<()> {    
-    let config = GenerateConfig {
-        model_type: rust_bert::pipelines::common::ModelType::GPT2,
-        max_length: Some(30),
-        min_length: 5,
-        length_penalty: 2.0,
-        early_stopping: true,
-        do_sample: false,
-        num_beams: 5,
-        temperature: 0.05,
-        ..Default::default()
-    };
-    let model = GPT2Generator::new(config)?;
+
+use crate::{answerers::{disabled_answerer::DisabledAnswerer, openai_answerer::OpenAiAnswerer}, errors::execution_error::{ExecutionError, ExecutionErrorReason}, model::{clarification::{Clarification, CommandSlot}, command::Command, command_action::{CommandAction, CommandGradientValue}, device_query::QueryOutcome, device_state::DeviceState, execution_outcome::{BatchOutcome, BatchSource, ExecutedIntent, ExecutionOutcome, ExecutionStatus}, local_answer::LocalAnswer, reminder::ReminderOutcome, routine::RoutineOutcome, schedule::{ScheduleOutcome, ScheduledCommand}, settings::{AnswererConfig, QuestionAnsweringConfig}, speech_request::SpeechRequest, timer::{TimerInfo, TimerOutcome}}, traits::question_answerer::QuestionAnswerer};
+#[cfg(feature = "transformers")]
+use crate::answerers::gpt2_answerer::Gpt2Answerer;
+
+use super::{classifier::ClassificationFailureReason, intent_executor::ExecutorOutput};
+
+pub fn main(config: QuestionAnsweringConfig, intent_rx: Receiver<ExecutorOutput>, feedback_tx: Sender<SpeechRequest>) -> Result<()> {
+    let answerer = load_answerer(&config)?;
 
     while let Ok(result) = intent_rx.recv() { 
         let message = match result {
-            Ok(intent) => feedback_for_intent(intent, &model),
+            Ok(intent) => feedback_for_intent(intent, answerer.as_ref(), config.max_words),
             Err(error) => feedback_for_error(error)
         };
+
+        // Routines and automations run without anyone asking, there isn't always something to say
+        if message.is_empty() {
+            continue;
+        }
+
         println!("Feedback message: '{}'\n", message);
-        if feedback_tx.send(message).is_err() {
+        if feedback_tx.send(SpeechRequest::Say(message)).is_err() {
             break;
         }
     }
@@ -36,6 +33,19 @@ pub fn main(intent_rx: Receiver<Result<Intent, ClassificationFailureReason>>, fe
     Ok(())
 }
 
+fn load_answerer(config: &QuestionAnsweringConfig) -> Result<Box<dyn QuestionAnswerer>> {
+    let answerer: Box<dyn QuestionAnswerer> = match config.backend {
+        #[cfg(feature = "transformers")]
+        AnswererConfig::Gpt2 => Box::new(Gpt2Answerer::new()?),
+        #[cfg(not(feature = "transformers"))]
+        AnswererConfig::Gpt2 => return Err(anyhow::anyhow!("The `gpt2` backend needs the `transformers` feature, use `openai` or `disabled` instead")),
+        AnswererConfig::OpenAi(ref backend) => Box::new(OpenAiAnswerer::new(backend, config)),
+        AnswererConfig::Disabled => Box::new(DisabledAnswerer)
+    };
+
+    Ok(answerer)
+}
+
 fn feedback_for_error(reason: ClassificationFailureReason) -> String {
     let str = match reason {
         ClassificationFailureReason::UnsupportedInstruction => "I don't know how to do this yet.",
@@ -46,16 +56,128 @@ fn feedback_for_error(reason: ClassificationFailureReason) -> String {
     str.to_string()
 }
 
-fn feedback_for_intent(intent: Intent, model: &GPT2Generator) -> String {
+fn feedback_for_intent(intent: ExecutedIntent, answerer: &dyn QuestionAnswerer, max_words: usize) -> String {
     match intent {
-        Intent::Command(ref command) => feedback_for_command(command),
-        Intent::Question(question) => answer_for_question(question, model)
+        ExecutedIntent::Command(ref outcome) => feedback_for_outcome(outcome),
+        ExecutedIntent::Batch(ref batch) => feedback_for_batch(batch),
+        ExecutedIntent::Question(question) => answer_for_question(question, answerer, max_words),
+        ExecutedIntent::Answer(ref answer) => local_answer(answer),
+        ExecutedIntent::Query(ref outcome) => answer_for_query(outcome),
+        ExecutedIntent::Timer(ref outcome) => feedback_for_timer(outcome),
+        ExecutedIntent::Schedule(ref outcome) => feedback_for_schedule(outcome),
+        ExecutedIntent::Routine(ref outcome) => feedback_for_routine(outcome),
+        ExecutedIntent::Reminder(ref outcome) => feedback_for_reminder(outcome),
+        ExecutedIntent::Clarification(ref clarification) => question_for_clarification(clarification)
+    }
+}
+
+// Only claims success for what actually happened
+fn feedback_for_outcome(outcome: &ExecutionOutcome) -> String {
+    let command = &outcome.command;
+    let target = format!("the {} in the {}", command.subject, command.location);
+
+    match outcome.status() {
+        ExecutionStatus::Success => match outcome.level() {
+            Some(level) => format!("I've {} {} to {} {}.", command.action, target, level, outcome.unit),
+            None => feedback_for_command(command)
+        },
+        ExecutionStatus::PartialSuccess => format!("I've {} {}, but {}.", command.action, target, failures(outcome)),
+        ExecutionStatus::Failure => match outcome.failed().collect::<Vec<_>>()[..] {
+            [] => format!("There's no {} set up in the {} yet.", command.subject, command.location),
+            [(_, error)] => format!("Sorry, {}.", failure(&target, command, error)),
+            _ => format!("Sorry, {}.", failures(outcome))
+        }
+    }
+}
+
+// One summary for the whole batch, going through every command would take ages
+fn feedback_for_batch(batch: &BatchOutcome) -> String {
+    let failures: Vec<String> = batch.outcomes
+        .iter()
+        .flat_map(device_failures)
+        .collect();
+
+    let (summary, nothing_set_up) = match (&batch.source, batch.outcomes.first()) {
+        (BatchSource::Scene(name), _) => (
+            format!("I've set up {}", name),
+            format!("There's nothing set up for {} yet.", name)
+        ),
+        (BatchSource::Group(name), Some(outcome)) => {
+            let changed = batch.outcomes.iter().map(|outcome| outcome.succeeded().count()).sum();
+            (
+                format!("I've {} {} {}", outcome.command.action, counted(changed, &outcome.command.subject.name), name),
+                format!("There's no {} set up {} yet.", outcome.command.subject, name)
+            )
+        }
+        (BatchSource::Group(name), None) => (
+            format!("I've taken care of {}", name),
+            format!("There's nothing set up {} yet.", name)
+        ),
+        (BatchSource::Routine { name, .. }, _) => (
+            format!("I've run the {} routine", name),
+            format!("There's nothing set up for the {} routine yet.", name)
+        ),
+        (BatchSource::Automation(name), _) => (
+            format!("I've run the {} automation", name),
+            format!("There's nothing set up for the {} automation yet.", name)
+        )
+    };
+
+    let message = match batch.status() {
+        ExecutionStatus::Success => format!("{}.", summary),
+        ExecutionStatus::PartialSuccess => format!("{}, but {}.", summary, listing(&failures, "and")),
+        ExecutionStatus::Failure if failures.is_empty() => nothing_set_up,
+        ExecutionStatus::Failure => format!("Sorry, {}.", listing(&failures, "and"))
+    };
+
+    // A routine that went fine only says its announcement, if it has one
+    match &batch.source {
+        BatchSource::Routine { announcement, .. } if batch.status() == ExecutionStatus::Success => announcement.clone().unwrap_or_default(),
+        BatchSource::Routine { announcement: Some(announcement), .. } => format!("{} {}", announcement, message),
+        // Nobody asked for an automation, so only devices that failed are worth saying out loud
+        BatchSource::Automation(name) if failures.is_empty() => {
+            if batch.status() != ExecutionStatus::Success {
+                println!("Automation '{}' had nothing to run: {}", name, message);
+            }
+            String::new()
+        }
+        BatchSource::Automation(_) => format!("Sorry, {}.", listing(&failures, "and")),
+        _ => message
+    }
+}
+
+fn feedback_for_routine(outcome: &RoutineOutcome) -> String {
+    match outcome {
+        RoutineOutcome::Paused(name) => format!("Okay, I've paused the {} routine.", name),
+        RoutineOutcome::Resumed(name) => format!("Okay, the {} routine is back on.", name),
+        RoutineOutcome::NotFound(name) => format!("There's no {} routine.", name)
+    }
+}
+
+fn failures(outcome: &ExecutionOutcome) -> String {
+    listing(&device_failures(outcome), "and")
+}
+
+fn device_failures(outcome: &ExecutionOutcome) -> Vec<String> {
+    outcome
+        .failed()
+        .map(|(device, error)| failure(&format!("the {}", device.spoken_name()), &outcome.command, error))
+        .collect()
+}
+
+fn failure(target: &str, command: &Command, error: &ExecutionError) -> String {
+    match error.reason {
+        ExecutionErrorReason::Unreachable => format!("I couldn't reach {}", target),
+        ExecutionErrorReason::TimedOut => format!("{} didn't respond in time", target),
+        ExecutionErrorReason::Rejected => format!("{} refused to {}", target, command.action.label()),
+        ExecutionErrorReason::Unsupported => format!("{} can't {}", target, command.action.label()),
+        ExecutionErrorReason::Failed => format!("something went wrong with {}", target)
     }
 }
 
 fn feedback_for_command(command: &Command) -> String {
     let action = command.action.to_string();
-    let subject_description = format!("the {}", command.subject.to_string());
+    let subject_description = format!("the {}", command.subject);
     let location_description = format!("in the {}", command.location);
 
     match rand::thread_rng().gen_range(0..=4) {
@@ -68,22 +190,238 @@ fn feedback_for_command(command: &Command) -> String {
     }
 }
 
-fn answer_for_question(question: String, model: &GPT2Generator) -> String {
-    let output = model.generate(Some(&[&question]), None);
-
-    println!("Question {:?}", output);
-    let empty_answer = "I don't know".to_string();
-    match output {
-        Ok(answer) => answer
-            .first()
-            .map(|answer| {
-                answer.text
-                .trim_start_matches(&question)
-                .split('.')
-                .next()
-                .unwrap_or(&empty_answer)
-                .to_string()
-            }).unwrap_or_else(|| empty_answer),
-        Err(_) => empty_answer
+fn answer_for_query(outcome: &QueryOutcome) -> String {
+    let query = &outcome.query;
+    // A group is named as is, e.g. "everywhere" or "downstairs"
+    let place = match outcome.is_group {
+        true => query.location.clone(),
+        false => format!("in the {}", query.location)
+    };
+
+    match &outcome.devices[..] {
+        [] => format!("There's no {} set up {} yet.", query.subject, place),
+        [(_, Some(state))] if !outcome.is_group => format!("The {} {} is {}.", query.subject, place, describe_state(state, &outcome.unit)),
+        [(_, None)] if !outcome.is_group => format!("I don't know what state the {} {} is in.", query.subject, place),
+        devices => {
+            let states: Vec<String> = devices
+                .iter()
+                .map(|(device, state)| match state {
+                    Some(state) => format!("the {} is {}", device.spoken_name(), describe_state(state, &outcome.unit)),
+                    None => format!("I don't know about the {}", device.spoken_name())
+                })
+                .collect();
+
+            format!("{}.", capitalized(&listing(&states, "and")))
+        }
+    }
+}
+
+fn capitalized(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new()
+    }
+}
+
+fn describe_state(state: &DeviceState, unit: &str) -> String {
+    match (state.on, state.level) {
+        (Some(false), _) => "off".to_string(),
+        (_, Some(level)) => format!("at {} {}", level, unit),
+        (Some(true), None) => "on".to_string(),
+        (None, None) => "in an unknown state".to_string()
+    }
+}
+
+fn local_answer(answer: &LocalAnswer) -> String {
+    match answer {
+        LocalAnswer::Time(now) => format!("It's {}.", now.format("%-I:%M %p")),
+        LocalAnswer::Date(now) => format!("Today is {}.", now.format("%A, %B %-d, %Y")),
+        LocalAnswer::Weekday(now) => format!("It's {}.", now.format("%A")),
+        LocalAnswer::Uptime(uptime) => format!("I've been running for {}.", describe_duration(*uptime)),
+        LocalAnswer::Capabilities(capabilities) => {
+            let mut abilities = vec![format!(
+                "control the {} in the {}",
+                listing(&capabilities.subjects, "and"),
+                listing(&capabilities.locations, "and")
+            )];
+            if !capabilities.scenes.is_empty() {
+                abilities.push(format!("set up {}", listing(&capabilities.scenes, "or")));
+            }
+            abilities.push("set timers and reminders".to_string());
+
+            format!("I can {}. Anything else you can just ask me.", listing(&abilities, "and"))
+        }
+    }
+}
+
+fn feedback_for_timer(outcome: &TimerOutcome) -> String {
+    match outcome {
+        TimerOutcome::Started(timer) => format!("I've set {} for {}.", timer_description(timer, "a timer"), describe_duration(timer.remaining)),
+        TimerOutcome::Cancelled(timer) => format!("I've cancelled {}.", timer_description(timer, "the timer")),
+        TimerOutcome::TimeLeft(timers) => {
+            let remaining: Vec<String> = timers
+                .iter()
+                .map(|timer| format!("{} on {}", describe_duration(timer.remaining), timer_description(timer, "the timer")))
+                .collect();
+
+            format!("There's {} left.", listing(&remaining, "and"))
+        }
+        TimerOutcome::NotFound(Some(name)) => format!("There's no {} timer running.", name),
+        TimerOutcome::NotFound(None) => "There's no timer running.".to_string(),
+        TimerOutcome::Ambiguous(timers) => {
+            let names: Vec<String> = timers.iter().map(|timer| timer_description(timer, "the one without a name")).collect();
+            format!("There are {} timers running, {}. Which one do you mean?", timers.len(), listing(&names, "and"))
+        }
+    }
+}
+
+fn feedback_for_reminder(outcome: &ReminderOutcome) -> String {
+    match outcome {
+        ReminderOutcome::Added(reminder) => format!("Okay, I'll remind you {} {}.", reminder.text, describe_time(reminder.due)),
+        ReminderOutcome::Snoozed(reminders) => match &reminders[..] {
+            [reminder] => format!("Okay, I'll remind you again {}.", describe_time(reminder.due)),
+            [first, ..] => format!("Okay, I'll remind you of all {} again {}.", reminders.len(), describe_time(first.due)),
+            [] => "There's no reminder going off right now.".to_string()
+        },
+        ReminderOutcome::Dismissed(reminders) => {
+            let texts: Vec<String> = reminders.iter().map(|reminder| reminder.text.clone()).collect();
+            format!("Okay, I won't remind you {} again.", listing(&texts, "or"))
+        }
+        ReminderOutcome::NothingRinging => "There's no reminder going off right now.".to_string()
+    }
+}
+
+fn feedback_for_schedule(outcome: &ScheduleOutcome) -> String {
+    match outcome {
+        ScheduleOutcome::Added(scheduled) => format!("Okay, I'll {}.", describe_scheduled(scheduled)),
+        ScheduleOutcome::Listed(scheduled) if scheduled.is_empty() => "There's nothing scheduled.".to_string(),
+        ScheduleOutcome::Listed(scheduled) => {
+            let plans: Vec<String> = scheduled.iter().map(describe_scheduled).collect();
+            format!("I'm going to {}.", listing(&plans, "and"))
+        }
+        ScheduleOutcome::Cancelled(cancelled) => match &cancelled[..] {
+            [] => "There's nothing scheduled like that.".to_string(),
+            [scheduled] => format!("Okay, I won't {}.", describe_scheduled(scheduled)),
+            _ => format!("I've cancelled {} scheduled commands.", cancelled.len())
+        }
+    }
+}
+
+// "turn off the light in the hallway at 7:00 PM"
+fn describe_scheduled(scheduled: &ScheduledCommand) -> String {
+    let command = &scheduled.command;
+    let action = match command.action {
+        CommandAction::Gradient(CommandGradientValue::Set(level)) => format!("set the {} in the {} to {}", command.subject, command.location, level),
+        action => format!("{} the {} in the {}", action.label(), command.subject, command.location)
+    };
+
+    format!("{} {}", action, describe_time(scheduled.due))
+}
+
+fn describe_time(time: DateTime<Local>) -> String {
+    let today = Local::now().date_naive();
+    let clock = time.format("%-I:%M %p");
+
+    match (time.date_naive() - today).num_days() {
+        0 => format!("at {}", clock),
+        1 => format!("tomorrow at {}", clock),
+        _ => format!("on {} at {}", time.format("%A"), clock)
+    }
+}
+
+fn timer_description(timer: &TimerInfo, unnamed: &str) -> String {
+    match timer.name {
+        Some(ref name) => format!("the {} timer", name),
+        None => unnamed.to_string()
+    }
+}
+
+// "1 hour and 5 minutes", seconds only matter for short timers
+fn describe_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+
+    let parts: Vec<String> = [(hours, "hour"), (minutes, "minute"), (if hours > 0 { 0 } else { seconds }, "second")]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, unit)| if count == 1 { format!("1 {}", unit) } else { format!("{} {}s", count, unit) })
+        .collect();
+
+    if parts.is_empty() {
+        "less than a second".to_string()
+    } else {
+        listing(&parts, "and")
+    }
+}
+
+fn question_for_clarification(clarification: &Clarification) -> String {
+    let options: Vec<String> = clarification.options
+        .iter()
+        .map(|option| match clarification.slot {
+            CommandSlot::Location | CommandSlot::Subject => format!("the {}", option.name),
+            CommandSlot::Action => option.name.clone()
+        })
+        .collect();
+
+    let choices = listing(&options, "or");
+
+    let pending = &clarification.pending;
+    match clarification.slot {
+        CommandSlot::Location => format!("Which room, {}?", choices),
+        CommandSlot::Subject => format!("Which one, {}?", choices),
+        CommandSlot::Action => match (&pending.subject, &pending.location) {
+            (Some(subject), Some(location)) => format!("Should I {} the {} in the {}?", choices, subject, location),
+            _ => format!("Should I {}?", choices)
+        }
+    }
+}
+
+// "the light" or "4 lights"
+fn counted(count: usize, name: &str) -> String {
+    match count {
+        1 => format!("the {}", name),
+        _ if name.ends_with('s') => format!("{} {}", count, name),
+        _ => format!("{} {}s", count, name)
+    }
+}
+
+// "a, b or c"
+fn listing(items: &[String], conjunction: &str) -> String {
+    match items.split_last() {
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => format!("{} {} {}", rest.join(", "), conjunction, last),
+        None => String::new()
+    }
+}
+
+fn answer_for_question(question: String, answerer: &dyn QuestionAnswerer, max_words: usize) -> String {
+    match answerer.answer(&question) {
+        Ok(answer) if !answer.trim().is_empty() => fit_for_speech(&answer, max_words),
+        Ok(_) => "I don't know".to_string(),
+        Err(e) => {
+            eprintln!("Could not answer '{}': {:?}", question, e);
+            "I don't know".to_string()
+        }
+    }
+}
+
+// Keeps as many whole sentences as fit, a single long sentence is cut off
+fn fit_for_speech(answer: &str, max_words: usize) -> String {
+    let mut fitted = String::new();
+    let mut count = 0;
+
+    for sentence in answer.split_inclusive(['.', '!', '?']) {
+        count += sentence.split_whitespace().count();
+        if count > max_words {
+            break;
+        }
+        fitted.push_str(sentence);
+    }
+
+    if fitted.trim().is_empty() {
+        answer.split_whitespace().take(max_words).collect::<Vec<&str>>().join(" ")
+    } else {
+        fitted.trim().to_string()
     }
 }
\ No newline at end of file
@@ -1,25 +1,406 @@
-use std::sync::mpsc::{Receiver, Sender};
+use std::{future::Future, sync::{mpsc::{Receiver, RecvTimeoutError, Sender}, Arc}, time::{Duration, Instant}};
 
-use crate::model::{command::Command, intent::Intent};
+use anyhow::Result;
+use chrono::Local;
+use futures::future::join_all;
+use tokio::runtime::Handle;
 
-use super::classifier::ClassifierOutput;
+use crate::{backends::backend_registry::BackendRegistry, core::{automation_engine::AutomationEngine, command_scheduler::CommandScheduler, commander::Commander, config_paths::ConfigPaths, local_skills::recognize_question, reminder_scheduler::ReminderScheduler, routine_runner::RoutineRunner, shared_commander::SharedCommander, state_store::StateStore, timer_scheduler::TimerScheduler}, errors::execution_error::ExecutionError, model::{command::Command, command_action::{CommandAction, CommandGradientValue, CommandSwitchValue}, command_subject::SubjectDefinition, device::Device, device_state::DeviceState, device_query::{DeviceQuery, QueryOutcome}, execution_outcome::{BatchOutcome, BatchSource, DeviceOutcome, ExecutedIntent, ExecutionOutcome}, intent::Intent, local_answer::{LocalAnswer, LocalQuestion}, speech_request::SpeechRequest, timer::TimerRequest}};
 
-pub fn main(classifier_rx: Receiver<ClassifierOutput>, executor_tx: Sender<ClassifierOutput>) {
-    while let Ok(result) = classifier_rx.recv() {
+use super::classifier::{ClassificationFailureReason, ClassifierOutput};
 
-        if let Ok(ref intent) = result {    
-            match intent {
-                Intent::Command(command) => execute_command(command),
-                Intent::Question(_) => {}
+pub type ExecutorOutput = Result<ExecutedIntent, ClassificationFailureReason>;
+
+const SCHEDULE_FILE: &str = "scheduled_commands.json";
+const PAUSED_ROUTINES_FILE: &str = "paused_routines.json";
+const REMINDERS_FILE: &str = "reminders.json";
+
+// How often we look for scheduled commands, routines and reminders that are due
+const SCHEDULE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub fn main(paths: Arc<ConfigPaths>, commanders: Arc<SharedCommander>, classifier_rx: Receiver<ClassifierOutput>, executor_tx: Sender<ExecutorOutput>, speech_tx: Sender<SpeechRequest>) -> Result<()> {
+    let runtime = Handle::current();
+    let started_at = Instant::now();
+    let states = Arc::new(StateStore::new());
+    let timers = TimerScheduler::new(runtime.clone(), speech_tx.clone());
+    let mut schedule = CommandScheduler::load(paths.data_dir().join(SCHEDULE_FILE));
+    let mut routines = RoutineRunner::load(paths.data_dir().join(PAUSED_ROUTINES_FILE));
+    let mut reminders = ReminderScheduler::load(paths.data_dir().join(REMINDERS_FILE));
+    let mut automations = AutomationEngine::new(&states);
+    let commander = commanders.current();
+    let mut runner = CommandRunner {
+        runtime,
+        executor_tx: executor_tx.clone(),
+        backends: Arc::new(BackendRegistry::new(&commander.backends, &commander.devices, states.clone())?),
+        commander,
+        states: states.clone()
+    };
+
+    loop {
+        let latest = commanders.current();
+        if !Arc::ptr_eq(&latest, &runner.commander) {
+            match BackendRegistry::new(&latest.backends, &latest.devices, states.clone()) {
+                Ok(reloaded) => runner.backends = Arc::new(reloaded),
+                Err(e) => eprintln!("Keeping the previous backends, could not set up the new ones: {}", e)
             };
+            runner.commander = latest;
+        }
+        let commander = runner.commander.clone();
+
+        // Scheduled commands are reported like any other so failures don't go unnoticed
+        for scheduled in schedule.take_due(Local::now()) {
+            println!("Running scheduled {:?}", scheduled.command);
+            runner.run_command(scheduled.command);
+        }
+
+        for routine in routines.take_due(Local::now(), &commander.routines) {
+            let commands = routine.commands
+                .iter()
+                .cloned()
+                .flat_map(|command| commander.expand(command))
+                .collect();
+            let source = BatchSource::Routine { name: routine.name.clone(), announcement: routine.announcement.clone() };
+
+            runner.run_batch(source, commands);
+        }
+
+        for automation in automations.take_triggered(&commander.automations, &commander, &states) {
+            println!("Automation '{}' fired on {:?}", automation.name, automation.trigger);
+            let commands = automation.commands
+                .iter()
+                .cloned()
+                .flat_map(|command| commander.expand(command))
+                .collect();
+            let source = BatchSource::Automation(automation.name.clone());
+
+            runner.run_batch(source, commands);
         }
 
-        if executor_tx.send(result).is_err() {
+        // Reminders go straight to the speaker, like the timer alarms
+        for reminder in reminders.take_due(Local::now()) {
+            println!("Reminder {} is due", reminder.id);
+            if speech_tx.send(SpeechRequest::Chime(format!("Don't forget {}.", reminder.text))).is_err() {
+                eprintln!("Could not say the reminder, the speech synthesizer is gone");
+            }
+        }
+
+        let intent = match classifier_rx.recv_timeout(SCHEDULE_POLL_INTERVAL) {
+            Ok(Ok(intent)) => intent,
+            Ok(Err(reason)) => {
+                if executor_tx.send(Err(reason)).is_err() {
+                    break;
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break
+        };
+
+        let executed = match intent {
+            Intent::Command(command) => {
+                runner.run_command(command);
+                continue;
+            }
+            Intent::Scene(name) => {
+                let commands = commander
+                    .scene(&name)
+                    .map(|scene| scene.commands.clone())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flat_map(|command| commander.expand(command))
+                    .collect();
+
+                runner.run_batch(BatchSource::Scene(name), commands);
+                continue;
+            }
+            Intent::Question(question) => match recognize_question(&question, &commander) {
+                Some(local) => answer_locally(local, started_at, &commander, &states, &timers),
+                None => ExecutedIntent::Question(question)
+            },
+            Intent::Query(query) => ExecutedIntent::Query(answer_query(query, &commander, &states)),
+            Intent::Timer(request) => ExecutedIntent::Timer(timers.handle(request)),
+            Intent::Schedule(request) => ExecutedIntent::Schedule(schedule.handle(request)),
+            Intent::Routine(request) => ExecutedIntent::Routine(routines.handle(request, &commander.routines)),
+            Intent::Reminder(request) => ExecutedIntent::Reminder(reminders.handle(request)),
+            Intent::Clarification(clarification) => ExecutedIntent::Clarification(clarification)
+        };
+
+        if executor_tx.send(Ok(executed)).is_err() {
             break;
         }
     }
+
+    Ok(())
+}
+
+// Devices can take seconds to answer, so commands run on the runtime and report back once
+// they're done instead of holding up the schedules, reminders and the next instruction
+struct CommandRunner {
+    runtime: Handle,
+    executor_tx: Sender<ExecutorOutput>,
+    commander: Arc<Commander>,
+    backends: Arc<BackendRegistry>,
+    states: Arc<StateStore>
+}
+
+impl CommandRunner {
+    fn run_command(&self, command: Command) {
+        let (commander, backends, states) = (self.commander.clone(), self.backends.clone(), self.states.clone());
+        self.spawn(async move { execute_intent_command(command, &commander, &backends, &states).await });
+    }
+
+    fn run_batch(&self, source: BatchSource, commands: Vec<Command>) {
+        let (commander, backends, states) = (self.commander.clone(), self.backends.clone(), self.states.clone());
+        self.spawn(async move { ExecutedIntent::Batch(execute_batch(source, commands, &commander, &backends, &states).await) });
+    }
+
+    fn spawn(&self, execution: impl Future<Output = ExecutedIntent> + Send + 'static) {
+        let executor_tx = self.executor_tx.clone();
+        self.runtime.spawn(async move {
+            // Nobody's listening anymore once Jarvis shuts down
+            let _ = executor_tx.send(Ok(execution.await));
+        });
+    }
+}
+
+// A command for a group of locations becomes one command per location
+async fn execute_intent_command(command: Command, commander: &Commander, backends: &BackendRegistry, states: &StateStore) -> ExecutedIntent {
+    match commander.group(&command.location) {
+        Some(group) => {
+            let source = BatchSource::Group(group.name);
+            ExecutedIntent::Batch(execute_batch(source, commander.expand(command), commander, backends, states).await)
+        }
+        None => ExecutedIntent::Command(execute_command(command, commander, backends, states).await)
+    }
+}
+
+async fn execute_batch(source: BatchSource, commands: Vec<Command>, commander: &Commander, backends: &BackendRegistry, states: &StateStore) -> BatchOutcome {
+    println!("Running {:?} as {} commands", source, commands.len());
+
+    let executions = commands
+        .into_iter()
+        .map(|command| execute_command(command, commander, backends, states));
+    let outcomes = join_all(executions).await;
+
+    BatchOutcome { source, outcomes }
+}
+
+async fn execute_command(command: Command, commander: &Commander, backends: &BackendRegistry, states: &StateStore) -> ExecutionOutcome {
+    let devices = commander.resolve(&command);
+    if devices.is_empty() {
+        println!("No devices registered for {:?}", command);
+    }
+
+    let subject = commander
+        .subject_definition(&command.subject)
+        .cloned()
+        .unwrap_or_else(|| SubjectDefinition::new(&command.subject.label, &command.subject.name, &[]));
+
+    // Every device may be in a different state, so "more" or "toggle" can mean something else for each
+    let actions: Vec<CommandAction> = devices
+        .iter()
+        .map(|device| {
+            let supports_levels = backends.get(&device.backend).is_some_and(|backend| backend.supports_levels());
+            concrete_action(command.action, states.get(&device.id).unwrap_or_default(), &subject, supports_levels)
+        })
+        .collect();
+
+    // All the devices are driven at once so a slow one doesn't hold up the rest
+    let executions = devices
+        .iter()
+        .zip(&actions)
+        .map(|(device, action)| execute_on_device(device, action, &subject, backends));
+    let results = join_all(executions).await;
+
+    let devices = devices
+        .into_iter()
+        .zip(actions)
+        .zip(results)
+        .map(|((device, action), result)| {
+            match result {
+                Ok(()) => {
+                    println!("Executed {:?} on '{}'", action, device.id);
+                    states.update(&device.id, |state| state.after(&action, &subject));
+                }
+                Err(ref e) => eprintln!("Could not execute {:?} on '{}': {:?}", action, device.id, e)
+            };
+
+            DeviceOutcome { device, action, result }
+        })
+        .collect();
+
+    ExecutionOutcome { command, devices, unit: subject.unit }
+}
+
+// Turns toggles and relative changes into concrete actions when we know where the
+// device is at. Otherwise the device is left to work it out on its own.
+fn concrete_action(action: CommandAction, state: DeviceState, subject: &SubjectDefinition, supports_levels: bool) -> CommandAction {
+    match (action, state.on, state.level) {
+        (CommandAction::Switch(CommandSwitchValue::Toggle), Some(on), _) => match on {
+            true => CommandAction::Switch(CommandSwitchValue::Off),
+            false => CommandAction::Switch(CommandSwitchValue::On)
+        },
+        (CommandAction::Gradient(CommandGradientValue::More), _, Some(level)) if supports_levels => {
+            CommandAction::Gradient(CommandGradientValue::Set(subject.clamp(level + subject.step)))
+        }
+        (CommandAction::Gradient(CommandGradientValue::Less), _, Some(level)) if supports_levels => {
+            CommandAction::Gradient(CommandGradientValue::Set(subject.clamp(level - subject.step)))
+        }
+        (CommandAction::Gradient(CommandGradientValue::Set(level)), _, _) => {
+            CommandAction::Gradient(CommandGradientValue::Set(subject.clamp(level)))
+        }
+        _ => action
+    }
 }
 
-fn execute_command(_command: &Command)  {
-    // println!("Executing {:?}", command);
-}
\ No newline at end of file
+fn answer_locally(question: LocalQuestion, started_at: Instant, commander: &Commander, states: &StateStore, timers: &TimerScheduler) -> ExecutedIntent {
+    println!("Answering {:?} locally", question);
+    let now = Local::now();
+
+    match question {
+        LocalQuestion::Time => ExecutedIntent::Answer(LocalAnswer::Time(now)),
+        LocalQuestion::Date => ExecutedIntent::Answer(LocalAnswer::Date(now)),
+        LocalQuestion::Weekday => ExecutedIntent::Answer(LocalAnswer::Weekday(now)),
+        LocalQuestion::Uptime => ExecutedIntent::Answer(LocalAnswer::Uptime(started_at.elapsed())),
+        LocalQuestion::Timers => ExecutedIntent::Timer(timers.handle(TimerRequest::TimeLeft { name: None })),
+        LocalQuestion::DeviceState(query) => ExecutedIntent::Query(answer_query(query, commander, states)),
+        LocalQuestion::Capabilities => ExecutedIntent::Answer(LocalAnswer::Capabilities(commander.capabilities()))
+    }
+}
+
+fn answer_query(query: DeviceQuery, commander: &Commander, states: &StateStore) -> QueryOutcome {
+    let unit = commander
+        .subject_definition(&query.subject)
+        .map(|definition| definition.unit.clone())
+        .unwrap_or_else(|| "percent".to_string());
+    let devices = commander
+        .devices_of(&query.location, &query.subject)
+        .into_iter()
+        .map(|device| {
+            let state = states.get(&device.id).filter(|state| state.is_known());
+            (device, state)
+        })
+        .collect();
+    let is_group = commander.group(&query.location).is_some();
+
+    QueryOutcome { query, devices, unit, is_group }
+}
+
+async fn execute_on_device(device: &Device, action: &CommandAction, subject: &SubjectDefinition, backends: &BackendRegistry) -> Result<(), ExecutionError> {
+    let backend = backends
+        .get(&device.backend)
+        .ok_or_else(|| ExecutionError::failed(format!("Unknown backend '{}'", device.backend)))?;
+
+    backend.execute(device, action, subject).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::command_map_parser::parse_command_map;
+
+    use super::*;
+
+    const COMMAND_MAP: &str = "\
+version: 1
+locations:
+  kitchen:
+    switch:
+      - light
+    gradient:
+      - temperature
+  hallway:
+    switch:
+      - light
+backends:
+  home:
+    type: simulated
+devices:
+  kitchen-light:
+    location: kitchen
+    subject: light
+    backend: home
+  kitchen-thermostat:
+    location: kitchen
+    subject: temperature
+    backend: home
+";
+
+    struct Home {
+        commander: Commander,
+        backends: BackendRegistry,
+        states: Arc<StateStore>
+    }
+
+    impl Home {
+        fn new(name: &str) -> Self {
+            let file = std::env::temp_dir().join(format!("jarvis-test-{}-{}.yaml", name, std::process::id()));
+            std::fs::write(&file, COMMAND_MAP).unwrap();
+            let (map, locations) = parse_command_map(&file.to_string_lossy()).unwrap();
+            let _ = std::fs::remove_file(&file);
+
+            let states = Arc::new(StateStore::new());
+            let backends = BackendRegistry::new(&map.backends, &map.devices, states.clone()).unwrap();
+
+            Home {
+                commander: Commander::new(map, locations),
+                backends,
+                states
+            }
+        }
+
+        async fn run(&self, location: &str, subject: &str, action: CommandAction) -> ExecutionOutcome {
+            let command = Command {
+                location: location.to_string(),
+                action,
+                subject: self.commander.subject(subject).unwrap()
+            };
+
+            execute_command(command, &self.commander, &self.backends, &self.states).await
+        }
+
+        fn state(&self, device: &str) -> DeviceState {
+            self.states.get(device).unwrap_or_default()
+        }
+    }
+
+    #[tokio::test]
+    async fn switching_is_remembered_for_toggles() {
+        let home = Home::new("switching");
+
+        let outcome = home.run("kitchen", "light", CommandAction::Switch(CommandSwitchValue::On)).await;
+        assert_eq!(outcome.devices.len(), 1);
+        assert!(outcome.devices[0].result.is_ok());
+        assert_eq!(home.state("kitchen-light").on, Some(true));
+
+        let outcome = home.run("kitchen", "light", CommandAction::Switch(CommandSwitchValue::Toggle)).await;
+        assert_eq!(outcome.devices[0].action, CommandAction::Switch(CommandSwitchValue::Off));
+        assert_eq!(home.state("kitchen-light").on, Some(false));
+    }
+
+    #[tokio::test]
+    async fn levels_stay_within_the_subjects_range() {
+        let home = Home::new("levels");
+
+        home.run("kitchen", "temperature", CommandAction::Gradient(CommandGradientValue::Set(40))).await;
+        assert_eq!(home.state("kitchen-thermostat").level, Some(28));
+
+        let outcome = home.run("kitchen", "temperature", CommandAction::Gradient(CommandGradientValue::More)).await;
+        assert_eq!(outcome.devices[0].action, CommandAction::Gradient(CommandGradientValue::Set(28)));
+        assert_eq!(outcome.unit, "degrees");
+
+        home.run("kitchen", "temperature", CommandAction::Gradient(CommandGradientValue::Less)).await;
+        assert_eq!(home.state("kitchen-thermostat").level, Some(27));
+
+        home.run("kitchen", "temperature", CommandAction::Gradient(CommandGradientValue::Min)).await;
+        assert_eq!(home.state("kitchen-thermostat").level, Some(16));
+    }
+
+    #[tokio::test]
+    async fn a_location_without_devices_runs_nothing() {
+        let home = Home::new("no-devices");
+
+        let outcome = home.run("hallway", "light", CommandAction::Switch(CommandSwitchValue::On)).await;
+        assert!(outcome.devices.is_empty());
+        assert_eq!(home.state("kitchen-light"), DeviceState::default());
+    }
+}
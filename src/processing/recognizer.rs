@@ -1,12 +1,24 @@
-use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{mpsc::{Receiver, Sender}, Arc};
 
-use crate::core::speech_recognizer::SpeechRecognizer;
+use anyhow::Result;
 
-pub fn main(chunker_rx: Receiver<Vec<f32>>, recognizer_tx: Sender<String>) {
+use crate::{core::{config_paths::ConfigPaths, shared_commander::SharedCommander, speech_recognizer::SpeechRecognizer}, model::utterance::Utterance};
 
-    let recognizer = SpeechRecognizer::new(&SpeechRecognizer::default_model_path());
+pub fn main(paths: Arc<ConfigPaths>, commanders: Arc<SharedCommander>, source: &str, chunker_rx: Receiver<Vec<f32>>, recognizer_tx: Sender<Utterance>) -> Result<()> {
+    let model_path = paths.whisper_model()?;
+    let mut recognizer = SpeechRecognizer::new(&model_path.to_string_lossy());
+
+    let mut commander = commanders.current();
+    recognizer.set_vocabulary(&commander.vocabulary());
 
     while let Ok(audio) = chunker_rx.recv() {
+        // New names from a reloaded command map need to reach the prompt too
+        let latest = commanders.current();
+        if !Arc::ptr_eq(&latest, &commander) {
+            commander = latest;
+            recognizer.set_vocabulary(&commander.vocabulary());
+        }
+
         let text = match recognizer.recognize(&audio) {
             Ok(text) => text,
             Err(_) => continue
@@ -17,11 +29,14 @@ pub fn main(chunker_rx: Receiver<Vec<f32>>, recognizer_tx: Sender<String>) {
             continue;
         }
 
-        println!("Recognized speech: '{}'", text);
-        if recognizer_tx.send(text).is_err() {
+        println!("Recognized speech from '{}': '{}'", source, text);
+        let utterance = Utterance { source: source.to_string(), text };
+        if recognizer_tx.send(utterance).is_err() {
             break;
         }
     }
+
+    Ok(())
 }
 
 fn is_noise(text: &str) -> bool {
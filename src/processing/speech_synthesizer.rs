@@ -1,17 +1,33 @@
-use std::{io::{Cursor, Read}, process::Stdio, sync::{mpsc::Receiver, Arc}};
+use std::{f32::consts::TAU, io::{Cursor, Read}, process::Stdio, sync::{mpsc::Receiver, Arc}};
 
 use anyhow::{Context, Result};
-use rodio::{Decoder, OutputStream, Sink};
+use rodio::{buffer::SamplesBuffer, Decoder, OutputStream, Sink};
 
-use crate::core::jarvis_signals::JarvisSignals;
+use crate::{core::jarvis_signals::JarvisSignals, model::speech_request::SpeechRequest};
 
-pub fn main(signals: Arc<JarvisSignals>, feedback_rx: Receiver<String>) -> Result<()> {
+const SAMPLE_RATE: u32 = 44100;
+const ALARM_PITCH: f32 = 880.0;
+const ALARM_BEEPS: usize = 4;
+// Two falling notes, E6 and C6
+const CHIME_PITCHES: [f32; 2] = [1318.5, 1046.5];
+
+pub fn main(signals: Arc<JarvisSignals>, feedback_rx: Receiver<SpeechRequest>) -> Result<()> {
     let (_stream, stream_handle) = OutputStream::try_default()?;
     let sink = Sink::try_new(&stream_handle)?;
 
     while !signals.is_shutdown() {
         let text = match feedback_rx.recv() {
-            std::result::Result::Ok(str) => str,
+            std::result::Result::Ok(SpeechRequest::Say(text)) => text,
+            std::result::Result::Ok(SpeechRequest::Alarm(text)) => {
+                signals.set_speaker_active(true);
+                sink.append(alarm_sound());
+                text
+            }
+            std::result::Result::Ok(SpeechRequest::Chime(text)) => {
+                signals.set_speaker_active(true);
+                sink.append(chime_sound());
+                text
+            }
             Err(_) => break
         };
 
@@ -34,7 +50,7 @@ pub fn main(signals: Arc<JarvisSignals>, feedback_rx: Receiver<String>) -> Resul
 
 fn get_audio_data(text: String) -> Result<Vec<u8>> {
     let mut child = std::process::Command::new("tts")
-        .args(&["--text", text.trim(), "--pipe_out"])
+        .args(["--text", text.trim(), "--pipe_out"])
         .stdout(Stdio::piped())
         .spawn()?;
 
@@ -50,6 +66,38 @@ fn get_audio_data(text: String) -> Result<Vec<u8>> {
     Ok(audio_data)
 }
 
+// A few short beeps, generated so there's no sound file to ship
+fn alarm_sound() -> SamplesBuffer<f32> {
+    let beep = SAMPLE_RATE as usize / 5;
+    let pause = SAMPLE_RATE as usize / 8;
+
+    let samples: Vec<f32> = (0..ALARM_BEEPS)
+        .flat_map(|_| {
+            let tone = (0..beep).map(|i| (TAU * ALARM_PITCH * i as f32 / SAMPLE_RATE as f32).sin() * 0.3);
+            tone.chain(std::iter::repeat_n(0.0, pause))
+        })
+        .collect();
+
+    SamplesBuffer::new(1, SAMPLE_RATE, samples)
+}
+
+// Each note fades out like a bell so it doesn't startle anyone
+fn chime_sound() -> SamplesBuffer<f32> {
+    let note = SAMPLE_RATE as usize / 2;
+
+    let samples: Vec<f32> = CHIME_PITCHES
+        .iter()
+        .flat_map(|pitch| {
+            (0..note).map(move |i| {
+                let time = i as f32 / SAMPLE_RATE as f32;
+                (TAU * pitch * time).sin() * (-6.0 * time).exp() * 0.3
+            })
+        })
+        .collect();
+
+    SamplesBuffer::new(1, SAMPLE_RATE, samples)
+}
+
 fn read_fallback_feedback() -> Vec<u8> {
     Vec::new()
 }
\ No newline at end of file
@@ -34,10 +34,8 @@ pub fn main(mic_rx: Receiver<Vec<f32>>, chunker_tx: Sender<Vec<f32>>) {
             if frame_energy > SPEECH_ENERGY_THRESHOLD {
                 speech_data.append(&mut frame);
 
-                if speech_data.len() >= MAX_BUFFER_SIZE {
-                    if chunker_tx.send(take(&mut speech_data)).is_err() {
-                        break
-                    }    
+                if speech_data.len() >= MAX_BUFFER_SIZE && chunker_tx.send(take(&mut speech_data)).is_err() {
+                    break
                 }
             } else if !speech_data.is_empty() {
                 // If it's an empty frame and we have some speech in the buffer
@@ -57,6 +55,6 @@ pub fn main(mic_rx: Receiver<Vec<f32>>, chunker_tx: Sender<Vec<f32>>) {
     }
 }
 
-fn frame_energy(frame: &Vec<f32>) -> f32 {
+fn frame_energy(frame: &[f32]) -> f32 {
     frame.iter().map(|val| val * val).sum::<f32>()
 }
\ No newline at end of file
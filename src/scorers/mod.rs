@@ -0,0 +1,3 @@
+pub mod rule_scorer;
+#[cfg(feature = "transformers")]
+pub mod zero_shot_scorer;
@@ -0,0 +1,91 @@
+use anyhow::Result;
+
+use crate::{core::spoken_time::words, model::{intent::Intent, label_score::LabelScore}, traits::label_scorer::LabelScorer};
+
+// Split up phrases like "turn the light on" still count, but a bit less than
+// "turn on" said in one go so "turn off the light on the porch" stays off
+const SPLIT_PHRASE_SCORE: f64 = 0.9;
+
+// "can you turn on the light" is a command, so no "can" or "could" here
+const QUESTION_WORDS: [&str; 12] = ["what", "what's", "who", "where", "when", "why", "how", "which", "is", "are", "do", "does"];
+// Only a question about one of these is about a device
+const STATE_WORDS: [&str; 10] = ["on", "off", "open", "opened", "closed", "set", "level", "status", "state", "running"];
+const SCHEDULE_WORDS: [&str; 4] = ["schedule", "scheduled", "planned", "later"];
+
+/// Looks for the labels in what was said, word for word. It needs a fraction
+/// of the memory of the zero-shot model, at the price of only understanding
+/// the names and aliases from the command map.
+pub struct RuleScorer;
+
+impl LabelScorer for RuleScorer {
+    fn score(&self, text: &str, labels: &[&str]) -> Result<Vec<LabelScore>> {
+        let said = words(text);
+
+        Ok(labels
+            .iter()
+            .map(|label| LabelScore { text: label.to_string(), score: score_label(&said, label) })
+            .collect())
+    }
+}
+
+fn score_label(said: &[String], label: &str) -> f64 {
+    let mentions = |candidates: &[&str]| said.iter().any(|word| candidates.contains(&word.as_str()));
+    let is_question = said.first().is_some_and(|word| QUESTION_WORDS.contains(&word.as_str()));
+
+    let matches = if Intent::is_label_question(label) {
+        is_question
+    } else if Intent::is_label_query(label) {
+        is_question && mentions(&STATE_WORDS)
+    } else if Intent::is_label_timer(label) {
+        mentions(&["timer", "timers"])
+    } else if Intent::is_label_schedule(label) {
+        mentions(&SCHEDULE_WORDS)
+    } else {
+        return phrase_score(said, &words(label));
+    };
+
+    if matches { 1.0 } else { 0.0 }
+}
+
+// 1 if the phrase was said as is, a bit less if other words came in between
+fn phrase_score(said: &[String], phrase: &[String]) -> f64 {
+    if phrase.is_empty() {
+        return 0.0;
+    }
+
+    if said.windows(phrase.len()).any(|window| window == phrase) {
+        return 1.0;
+    }
+
+    let mut remaining = phrase.iter().peekable();
+    for word in said {
+        if remaining.peek() == Some(&word) {
+            remaining.next();
+        }
+    }
+
+    if remaining.peek().is_none() { SPLIT_PHRASE_SCORE } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scores(text: &str, labels: &[&str]) -> Vec<f64> {
+        RuleScorer.score(text, labels).unwrap().into_iter().map(|label| label.score).collect()
+    }
+
+    #[test]
+    fn a_phrase_said_in_one_go_beats_a_split_one() {
+        // "on the porch" also makes "turn on" out of "turn off the light on the porch"
+        assert_eq!(scores("turn off the light on the porch", &["turn off", "turn on"]), [1.0, SPLIT_PHRASE_SCORE]);
+        assert_eq!(scores("Turn the living-room light on.", &["living room", "turn on", "kitchen"]), [1.0, SPLIT_PHRASE_SCORE, 0.0]);
+    }
+
+    #[test]
+    fn only_questions_about_a_state_are_device_queries() {
+        assert_eq!(scores("is the kitchen light on", &["question", "device status"]), [1.0, 1.0]);
+        assert_eq!(scores("what is the capital of france", &["question", "device status"]), [1.0, 0.0]);
+        assert_eq!(scores("can you turn on the light", &["question", "device status"]), [0.0, 0.0]);
+    }
+}
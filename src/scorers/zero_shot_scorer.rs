@@ -0,0 +1,35 @@
+use anyhow::Result;
+use rust_bert::pipelines::zero_shot_classification::{ZeroShotClassificationConfig, ZeroShotClassificationModel};
+
+use crate::{model::label_score::LabelScore, traits::label_scorer::LabelScorer};
+
+/// BART zero-shot classification. It understands instructions it has never
+/// seen but takes most of Jarvis' memory.
+pub struct ZeroShotScorer {
+    model: ZeroShotClassificationModel
+}
+
+impl ZeroShotScorer {
+    pub fn new() -> Result<Self> {
+        let config = ZeroShotClassificationConfig {
+            model_type: rust_bert::pipelines::common::ModelType::Bart,
+            ..Default::default()
+        };
+
+        Ok(ZeroShotScorer { model: ZeroShotClassificationModel::new(config)? })
+    }
+}
+
+impl LabelScorer for ZeroShotScorer {
+    fn score(&self, text: &str, labels: &[&str]) -> Result<Vec<LabelScore>> {
+        let output = self.model.predict_multilabel([text], labels, None, 128)?;
+
+        Ok(output
+            .into_iter()
+            .next()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|label| LabelScore { text: label.text, score: label.score })
+            .collect())
+    }
+}
@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+
+use crate::{errors::execution_error::ExecutionError, model::{command_action::CommandAction, command_subject::SubjectDefinition, device::Device}};
+
+/// Something that knows how to drive physical devices, e.g. over MQTT
+#[async_trait]
+pub trait ExecutorBackend: Send + Sync {
+    /// Whether the backend can set a concrete level. If not, relative
+    /// changes are left to the device.
+    fn supports_levels(&self) -> bool {
+        true
+    }
+
+    /// `subject` is what the device is, with the step and range its levels move in
+    async fn execute(&self, device: &Device, action: &CommandAction, subject: &SubjectDefinition) -> Result<(), ExecutionError>;
+}
\ No newline at end of file
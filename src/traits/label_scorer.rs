@@ -0,0 +1,9 @@
+use anyhow::Result;
+
+use crate::model::label_score::LabelScore;
+
+/// Scores each label against a text on its own, so several labels can fit
+/// at once. The classifier picks the intent and the command slots from it.
+pub trait LabelScorer {
+    fn score(&self, text: &str, labels: &[&str]) -> Result<Vec<LabelScore>>;
+}
@@ -1 +1,4 @@
-pub mod labelable;
\ No newline at end of file
+pub mod labelable;
+pub mod executor_backend;
+pub mod question_answerer;
+pub mod label_scorer;
\ No newline at end of file
@@ -0,0 +1,6 @@
+use anyhow::Result;
+
+/// Answers general questions, the ones Jarvis can't answer from what it knows itself
+pub trait QuestionAnswerer {
+    fn answer(&self, question: &str) -> Result<String>;
+}